@@ -1,27 +1,171 @@
 use crate::error::TizenError;
-use crate::helpers::run_command;
-use crate::tizen_env::TizenEnv;
+use crate::helpers::make_command;
+use crate::tizen_env::{TizenApplication, TizenEnv};
 use clap::ArgMatches;
+use colored::*;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::Stdio;
+
+pub struct BuiltExecutable {
+    pub name: String,
+    pub path: PathBuf,
+}
 
 pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
+    build(tizen_env, args).map(|_| 0)
+}
+
+pub fn build(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<Vec<BuiltExecutable>, TizenError> {
     let mut cargo_args: Vec<String> = vec![
         "build".to_string(),
         format!("--target={}", &tizen_env.rust_triple),
+        "--message-format=json-render-diagnostics".to_string(),
     ];
 
     if tizen_env.is_release {
         cargo_args.push("--release".to_string());
     }
 
-    let mut handle = run_command(&tizen_env, &args, "cargo", &cargo_args, None, true, None);
+    if let Some(cargo_package) = &tizen_env.cargo_package {
+        cargo_args.push(format!("--package={}", cargo_package));
+    }
+
+    if let Some(bin_name) = args.value_of("bin") {
+        cargo_args.push(format!("--bin={}", bin_name));
+    }
+
+    run_cargo(tizen_env, args, &cargo_args, None, false)
+}
+
+// Runs a cargo command with json messages and returns the executables it built,
+// the test harness binaries instead of the bins when is_test is set
+pub fn run_cargo(
+    tizen_env: &TizenEnv,
+    args: &ArgMatches,
+    cargo_args: &[String],
+    last_args: Option<Vec<String>>,
+    is_test: bool,
+) -> Result<Vec<BuiltExecutable>, TizenError> {
+    let mut handle = match make_command(tizen_env, args, "cargo", cargo_args, last_args, true, None)
+        .stdout(Stdio::piped())
+        .spawn()
+    {
+        Ok(handle) => handle,
+        Err(_) => {
+            return Err(TizenError {
+                message: "Failed to launch cargo".to_string(),
+            })
+        }
+    };
+
+    let mut executables: Vec<BuiltExecutable> = vec![];
+
+    if let Some(stdout) = handle.stdout.take() {
+        for line in BufReader::new(stdout).lines() {
+            let line = line?;
+
+            match serde_json::from_str::<serde_json::Value>(&line) {
+                Ok(message) => {
+                    if let Some(executable) = parse_executable(&message, is_test) {
+                        println!("Built {}", executable.path.to_str().unwrap_or("").yellow());
+                        executables.push(executable);
+                    }
+                }
+                Err(_) => println!("{}", &line),
+            }
+        }
+    }
 
     let exit_code = handle.wait().expect("Failed to wait on child");
 
     if !exit_code.success() {
         return Err(TizenError {
-            message: "cargo tizen build failed!".to_string(),
+            message: format!("cargo tizen {} failed!", &cargo_args[0]),
         });
     }
 
-    Ok(exit_code.code().unwrap())
+    Ok(executables)
+}
+
+pub fn select_executable<'a>(
+    tizen_env: &TizenEnv,
+    args: &ArgMatches,
+    executables: &'a [BuiltExecutable],
+    application: &TizenApplication,
+) -> Result<&'a BuiltExecutable, TizenError> {
+    let exec_name = application.exec_name();
+    let by_exec = executables.iter().find(|e| e.name == exec_name);
+
+    let has_many_apps = tizen_env.applications.len() > 1;
+
+    let selected = match args.value_of("bin") {
+        // Each application is packaged with its own binary, one --bin can't choose them all
+        Some(bin_name) if has_many_apps => {
+            return Err(TizenError {
+                message: format!(
+                    "--bin {} can't be used with {} applications in the manifest, each one is packaged with the binary named like its exec",
+                    bin_name,
+                    tizen_env.applications.len()
+                ),
+            })
+        }
+        None if has_many_apps => by_exec,
+        Some(bin_name) => executables.iter().find(|e| e.name == bin_name),
+        None => by_exec
+            .or_else(|| {
+                executables
+                    .iter()
+                    .find(|e| e.name == tizen_env.cargo_pkg_name)
+            })
+            .or(if executables.len() == 1 {
+                executables.first()
+            } else {
+                None
+            }),
+    };
+
+    match selected {
+        Some(executable) => Ok(executable),
+        None => Err(TizenError {
+            message: format!(
+                "Can't find the binary for exec '{}', {}, available binaries: {}",
+                exec_name,
+                if has_many_apps {
+                    "name a cargo binary like the exec of each application"
+                } else {
+                    "use --bin to select one"
+                },
+                executables
+                    .iter()
+                    .map(|e| e.name.clone())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }),
+    }
+}
+
+fn parse_executable(message: &serde_json::Value, is_test: bool) -> Option<BuiltExecutable> {
+    if message["reason"] != "compiler-artifact" {
+        return None;
+    }
+
+    let is_selected = if is_test {
+        message["profile"]["test"] == true
+    } else {
+        message["target"]["kind"]
+            .as_array()
+            .map(|kinds| kinds.iter().any(|kind| kind == "bin"))
+            .unwrap_or(false)
+    };
+
+    if !is_selected {
+        return None;
+    }
+
+    Some(BuiltExecutable {
+        name: message["target"]["name"].as_str()?.to_string(),
+        path: PathBuf::from(message["executable"].as_str()?),
+    })
 }
@@ -0,0 +1,105 @@
+use crate::error::TizenError;
+use crate::manifest::{check_manifest, manifest_path, Diagnostic};
+use crate::tizen_env::TizenEnv;
+use clap::ArgMatches;
+use colored::*;
+use std::path::Path;
+use std::process::Command;
+
+pub fn run(tizen_env: &TizenEnv, _args: &ArgMatches) -> Result<i32, TizenError> {
+    let bin_names = cargo_bin_names(&tizen_env.base_path)?;
+    let diagnostics = check_manifest(tizen_env, Some(&bin_names))?;
+
+    report(tizen_env, &diagnostics)?;
+
+    println!("{}", "Manifest is valid".green().bold());
+
+    Ok(0)
+}
+
+pub fn report(tizen_env: &TizenEnv, diagnostics: &[Diagnostic]) -> Result<(), TizenError> {
+    let file_name = match tizen_env.generated_manifest {
+        Some(_) => "tizen-manifest.xml (generated from Cargo.toml)".to_string(),
+        None => manifest_path(&tizen_env.base_path)
+            .to_str()
+            .unwrap_or("")
+            .to_string(),
+    };
+
+    for diagnostic in diagnostics.iter() {
+        diagnostic.print(&file_name);
+    }
+
+    let error_count = diagnostics.iter().filter(|d| d.is_error()).count();
+
+    if error_count > 0 {
+        return Err(TizenError {
+            message: format!(
+                "tizen-manifest.xml has {} error{}",
+                error_count,
+                if error_count > 1 { "s" } else { "" }
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+fn cargo_bin_names(base_path: &Path) -> Result<Vec<String>, TizenError> {
+    let mut cargo_toml_path = base_path.to_path_buf();
+    cargo_toml_path.push("Cargo.toml");
+
+    let output = Command::new("cargo")
+        .args([
+            "metadata",
+            "--no-deps",
+            "--format-version=1",
+            "--manifest-path",
+        ])
+        .arg(&cargo_toml_path)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(TizenError {
+            message: format!(
+                "cargo metadata failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        });
+    }
+
+    let metadata: serde_json::Value = match serde_json::from_slice(&output.stdout) {
+        Ok(metadata) => metadata,
+        Err(_) => {
+            return Err(TizenError {
+                message: "Can't parse cargo metadata output".to_string(),
+            })
+        }
+    };
+
+    let canonical_path = cargo_toml_path.canonicalize().ok();
+    let package = metadata["packages"].as_array().and_then(|packages| {
+        packages.iter().find(|package| {
+            package["manifest_path"]
+                .as_str()
+                .and_then(|path| Path::new(path).canonicalize().ok())
+                == canonical_path
+        })
+    });
+
+    Ok(package
+        .and_then(|package| package["targets"].as_array())
+        .map(|targets| {
+            targets
+                .iter()
+                .filter(|target| {
+                    target["kind"]
+                        .as_array()
+                        .map(|kinds| kinds.iter().any(|kind| kind == "bin"))
+                        .unwrap_or(false)
+                })
+                .filter_map(|target| target["name"].as_str().map(|name| name.to_string()))
+                .collect()
+        })
+        .unwrap_or_default())
+}
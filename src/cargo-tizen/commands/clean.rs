@@ -13,7 +13,11 @@ pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
         cargo_args.push("--release".to_string());
     }
 
-    let mut handle = run_command(&tizen_env, &args, "cargo", &cargo_args, None, true, None);
+    if let Some(cargo_package) = &tizen_env.cargo_package {
+        cargo_args.push(format!("--package={}", cargo_package));
+    }
+
+    let mut handle = run_command(tizen_env, args, "cargo", &cargo_args, None, true, None);
 
     let exit_code = handle.wait().expect("Failed to wait on child");
 
@@ -5,9 +5,17 @@ use cli_table::{print_stdout, Cell, Table};
 use colored::*;
 
 pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
+    if args.is_present("runner") {
+        return show_runner(tizen_env);
+    }
+
+    if args.is_present("files") {
+        return show_files(tizen_env);
+    }
+
     match args.value_of("env_key") {
         Some(str_value) => show_detail(tizen_env, String::from(str_value)),
-        None => list_configs(&tizen_env),
+        None => list_configs(tizen_env),
     }
 }
 
@@ -75,6 +83,61 @@ fn list_configs(tizen_env: &TizenEnv) -> Result<i32, TizenError> {
     Ok(0)
 }
 
+// cargo calls the runner with the binary and its args, -- keeps them away from clap
+fn show_runner(tizen_env: &TizenEnv) -> Result<i32, TizenError> {
+    println!(
+        "{}",
+        "Add to .cargo/config.toml to run cargo run/test on the target:"
+            .green()
+            .bold()
+    );
+
+    // exec builds the whole TizenEnv, so it needs a package with a manifest
+    println!(
+        "# exec reads the config of the package in the current folder, so cargo run fails\n# from a virtual workspace root, run it from the package folder"
+    );
+
+    for (rust_triple, is_emulator) in tizen_env.runner_targets.iter() {
+        println!(
+            "\n[target.{}]\n{}runner = [\"cargo-tizen\", \"exec\", {}\"--\"]",
+            rust_triple,
+            if *is_emulator {
+                ""
+            } else {
+                "# without -e the target follows is_emulator, keep it false or auto for the device\n"
+            },
+            if *is_emulator { "\"-e\", " } else { "" }
+        );
+    }
+
+    Ok(0)
+}
+
+fn show_files(tizen_env: &TizenEnv) -> Result<i32, TizenError> {
+    let synced_files = tizen_env.synced_files()?;
+
+    println!(
+        "{} {}",
+        "Files packaged from".green().bold(),
+        tizen_env.sync_files.join(",").yellow()
+    );
+
+    for synced_file in synced_files.iter() {
+        let source = synced_file
+            .source
+            .strip_prefix(&tizen_env.base_path)
+            .unwrap_or(&synced_file.source);
+
+        println!(
+            "{} <- {}",
+            synced_file.dest.to_str().unwrap_or(""),
+            source.to_str().unwrap_or("")
+        );
+    }
+
+    Ok(0)
+}
+
 fn from_to_s(config_from: &ConfigFrom) -> String {
     match config_from {
         ConfigFrom::Env => "env".to_string(),
@@ -82,5 +145,6 @@ fn from_to_s(config_from: &ConfigFrom) -> String {
         ConfigFrom::Cargo => "cargo file".to_string(),
         ConfigFrom::Manifest => "manifest".to_string(),
         ConfigFrom::Default => "default".to_string(),
+        ConfigFrom::Detected => "detected from target".to_string(),
     }
 }
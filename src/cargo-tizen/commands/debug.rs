@@ -0,0 +1,203 @@
+use crate::commands;
+use crate::commands::build::select_executable;
+use crate::error::TizenError;
+use crate::sdb::{shell_quote, SdbClient};
+use crate::tizen_env::{TizenApplication, TizenEnv};
+use clap::ArgMatches;
+use colored::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// launch_app starts the app under the gdbserver found in this folder
+const SDK_TOOLS_DIR: &str = "/home/owner/share/tmp/sdk_tools";
+
+pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
+    let application = tizen_env.find_application(args.value_of("app"))?;
+
+    if !application.app_type.can_launch() {
+        return Err(TizenError {
+            message: format!(
+                "Only ui and service applications can be debugged, {} is a {}",
+                &application.app_id,
+                application.app_type.element()
+            ),
+        });
+    }
+
+    let port = match args.value_of("port").unwrap_or("").parse::<u16>() {
+        Ok(port) => port,
+        Err(_) => {
+            return Err(TizenError {
+                message: format!("Invalid port {}", args.value_of("port").unwrap_or("")),
+            })
+        }
+    };
+
+    let gdb_bin = match tizen_env.gdb_bin() {
+        Some(gdb_bin) => gdb_bin,
+        None => {
+            return Err(TizenError {
+                message: format!(
+                    "Can't find gdb in {}",
+                    tizen_env.toolchain_path.to_str().unwrap_or("")
+                ),
+            })
+        }
+    };
+
+    let executables = commands::build::build(tizen_env, args)?;
+    let executable = select_executable(tizen_env, args, &executables, application)?;
+    commands::package::package(tizen_env, args, &executables)?;
+    commands::install::run(tizen_env, args)?;
+
+    let sdb = SdbClient::new(tizen_env);
+    let device = sdb.select_device()?;
+
+    push_gdbserver(tizen_env, &sdb, &device.serial)?;
+    launch_gdbserver(&sdb, &device.serial, application, port)?;
+    sdb.forward(&device.serial, port, port)?;
+
+    run_gdb(tizen_env, &gdb_bin, &executable.path, port)
+}
+
+fn push_gdbserver(tizen_env: &TizenEnv, sdb: &SdbClient, serial: &str) -> Result<(), TizenError> {
+    let gdbserver_bin = format!("{}/gdbserver/gdbserver", SDK_TOOLS_DIR);
+
+    if sdb.shell(serial, &format!("test -x {}", gdbserver_bin), &mut |_| {})? == 0 {
+        return Ok(());
+    }
+
+    let tar_path = find_gdbserver_tar(tizen_env)?;
+    let remote_tar = format!("{}/gdbserver.tar", SDK_TOOLS_DIR);
+
+    println!(
+        "{} {}",
+        "Pushing".green().bold(),
+        tar_path.to_str().unwrap_or("").yellow()
+    );
+
+    sdb.shell(serial, &format!("mkdir -p {}", SDK_TOOLS_DIR), &mut |_| {})?;
+    sdb.push(serial, &tar_path, &remote_tar, 0o644)?;
+
+    let extract_command = format!(
+        "tar -xf {} -C {} && rm {}",
+        remote_tar, SDK_TOOLS_DIR, remote_tar
+    );
+    let mut output = String::new();
+    let exit_code = sdb.shell(serial, &extract_command, &mut |line| {
+        output.push_str(line);
+        output.push('\n');
+    })?;
+
+    if exit_code != 0 {
+        return Err(TizenError {
+            message: format!("Failed to extract gdbserver: {}", output.trim()),
+        });
+    }
+
+    Ok(())
+}
+
+// Tizen Studio ships one gdbserver_<version>_<arch>.tar per arch, the newest is used
+fn find_gdbserver_tar(tizen_env: &TizenEnv) -> Result<PathBuf, TizenError> {
+    let arch = match tizen_env.tizen_triple.as_str() {
+        "aarch64-linux-gnu" => "aarch64",
+        "x86_64-linux-gnu" => "x86_64",
+        triple if triple.starts_with("arm") => "armel",
+        _ => "i386",
+    };
+
+    let mut on_demand_path = tizen_env.studio_path.clone();
+    on_demand_path.push("tools");
+    on_demand_path.push("on-demand");
+
+    let suffix = format!("_{}.tar", arch);
+    let mut tars: Vec<PathBuf> = fs::read_dir(&on_demand_path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry_result| entry_result.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .map(|name| name.starts_with("gdbserver_") && name.ends_with(&suffix))
+                        .unwrap_or(false)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    tars.sort();
+
+    match tars.pop() {
+        Some(tar_path) => Ok(tar_path),
+        None => Err(TizenError {
+            message: format!(
+                "Can't find gdbserver for {} in {}, install it from the Tizen Studio package manager",
+                arch,
+                on_demand_path.to_str().unwrap_or("")
+            ),
+        }),
+    }
+}
+
+fn launch_gdbserver(
+    sdb: &SdbClient,
+    serial: &str,
+    application: &TizenApplication,
+    port: u16,
+) -> Result<(), TizenError> {
+    let launch_command = format!(
+        "launch_app {} __AUL_SDK__ DEBUG __DLP_DEBUG_ARG__ :{}",
+        shell_quote(&application.app_id),
+        port
+    );
+    println!("Running: {}", &launch_command.green().bold());
+
+    let exit_code = sdb.shell(serial, &launch_command, &mut |line| println!("{}", line))?;
+
+    if exit_code != 0 {
+        return Err(TizenError {
+            message: format!("Failed to launch {} under gdbserver", &application.app_id),
+        });
+    }
+
+    Ok(())
+}
+
+fn run_gdb(
+    tizen_env: &TizenEnv,
+    gdb_bin: &Path,
+    bin_path: &Path,
+    port: u16,
+) -> Result<i32, TizenError> {
+    let gdb_args = vec![
+        "-q".to_string(),
+        "-ex".to_string(),
+        format!(
+            "set sysroot {}",
+            tizen_env.rootstrap_path.to_str().unwrap_or("")
+        ),
+        "-ex".to_string(),
+        format!("target remote 127.0.0.1:{}", port),
+        bin_path.to_str().unwrap_or("").to_string(),
+    ];
+
+    println!(
+        "Running: {} {}",
+        gdb_bin.to_str().unwrap_or("").green().bold(),
+        gdb_args.join(" ").green().bold()
+    );
+
+    // Ctrl-C interrupts the debugged program, it must not kill cargo-tizen
+    if ctrlc::set_handler(|| {}).is_err() {
+        return Err(TizenError {
+            message: "Can't listen for Ctrl-C".to_string(),
+        });
+    }
+
+    let status = Command::new(gdb_bin).args(&gdb_args).status()?;
+
+    Ok(status.code().unwrap_or(1))
+}
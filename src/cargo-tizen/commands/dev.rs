@@ -4,8 +4,15 @@ use crate::TizenEnv;
 use clap::ArgMatches;
 
 pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
-    commands::build::run(&tizen_env, &args)
-        .and_then(|_| commands::package::run(&tizen_env, &args))
-        .and_then(|_| commands::install::run(&tizen_env, &args))
-        .and_then(|_| commands::run::run(&tizen_env, &args))
+    // The emulator boots while building, it is waited before installing
+    let booting_emulator = commands::emulator::launch_configured(tizen_env)?;
+
+    commands::build::build(tizen_env, args)
+        .and_then(|executables| commands::package::package(tizen_env, args, &executables))
+        .and_then(|_| match &booting_emulator {
+            Some(name) => commands::emulator::wait_for(tizen_env, name).map(|_| 0),
+            None => Ok(0),
+        })
+        .and_then(|_| commands::install::run(tizen_env, args))
+        .and_then(|_| commands::run::run(tizen_env, args))
 }
@@ -0,0 +1,57 @@
+use crate::error::TizenError;
+use crate::sdb::{arch_triple, SdbClient};
+use crate::tizen_env::TizenEnv;
+use clap::ArgMatches;
+use cli_table::{print_stdout, Cell, Table};
+use colored::*;
+
+pub fn run(tizen_env: &TizenEnv, _args: &ArgMatches) -> Result<i32, TizenError> {
+    let sdb = SdbClient::new(tizen_env);
+    let devices = sdb.devices()?;
+
+    if devices.is_empty() {
+        println!(
+            "{}",
+            "No device or emulator connected to sdb".yellow().bold()
+        );
+        return Ok(0);
+    }
+
+    let selected = sdb.select_device().ok().map(|device| device.serial);
+
+    let table = devices
+        .iter()
+        .map(|device| {
+            let arch = match device.state.as_str() {
+                "device" => sdb.arch(&device.serial).unwrap_or_default(),
+                _ => "".to_string(),
+            };
+
+            vec![
+                device.serial.clone().cell(),
+                device.state.clone().cell(),
+                device.name.clone().cell(),
+                arch.clone().cell(),
+                arch_triple(&arch).unwrap_or("").cell(),
+                match selected.as_ref() == Some(&device.serial) {
+                    true => "yes",
+                    false => "",
+                }
+                .cell(),
+            ]
+        })
+        .collect::<Vec<_>>()
+        .table()
+        .title(vec![
+            "serial".cell(),
+            "state".cell(),
+            "name".cell(),
+            "arch".cell(),
+            "tizen triple".cell(),
+            "selected".cell(),
+        ]);
+
+    assert!(print_stdout(table).is_ok());
+
+    Ok(0)
+}
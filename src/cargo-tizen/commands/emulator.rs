@@ -0,0 +1,275 @@
+use crate::error::TizenError;
+use crate::helpers::run_command;
+use crate::sdb::{SdbClient, SdbDevice};
+use crate::tizen_env::TizenEnv;
+use clap::ArgMatches;
+use cli_table::{print_stdout, Cell, Table};
+use colored::*;
+use std::io::Read;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const BOOT_TIMEOUT: Duration = Duration::from_secs(300);
+const STOP_TIMEOUT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
+    match args.subcommand() {
+        ("list", Some(_)) => list(tizen_env),
+        ("start", Some(start_args)) => {
+            let name = emulator_name(tizen_env, start_args.value_of("name"))?;
+
+            if launch(tizen_env, &name)? {
+                wait_for(tizen_env, &name)?;
+            }
+
+            Ok(0)
+        }
+        ("stop", Some(stop_args)) => {
+            let name = emulator_name(tizen_env, stop_args.value_of("name"))?;
+            stop(tizen_env, &name)
+        }
+        ("create", Some(create_args)) => {
+            let em_cli_args = vec![
+                "create".to_string(),
+                "-n".to_string(),
+                create_args.value_of("name").unwrap_or("").to_string(),
+                "-p".to_string(),
+                create_args.value_of("platform").unwrap_or("").to_string(),
+            ];
+
+            let mut handle = run_command(
+                tizen_env,
+                create_args,
+                &tizen_env.em_cli_bin,
+                &em_cli_args,
+                None,
+                false,
+                None,
+            );
+
+            let exit_code = handle.wait().expect("Failed to wait on child");
+
+            if !exit_code.success() {
+                return Err(TizenError {
+                    message: format!(
+                        "cargo tizen emulator create failed! list the platforms with {} list-platform",
+                        &tizen_env.em_cli_bin
+                    ),
+                });
+            }
+
+            Ok(0)
+        }
+        _ => Err(TizenError {
+            message: "No command matched!".to_string(),
+        }),
+    }
+}
+
+// Boots tizen.emulator.name for dev -e when it isn't running, returns the name to wait for
+pub fn launch_configured(tizen_env: &TizenEnv) -> Result<Option<String>, TizenError> {
+    if !tizen_env.is_emulator || tizen_env.emulator_name.is_empty() {
+        return Ok(None);
+    }
+
+    match launch(tizen_env, &tizen_env.emulator_name)? {
+        true => Ok(Some(tizen_env.emulator_name.clone())),
+        false => Ok(None),
+    }
+}
+
+pub fn wait_for(tizen_env: &TizenEnv, name: &str) -> Result<SdbDevice, TizenError> {
+    let sdb = SdbClient::new(tizen_env);
+    let started_at = Instant::now();
+
+    println!(
+        "{} {}",
+        "Waiting for emulator".green().bold(),
+        name.yellow()
+    );
+
+    while started_at.elapsed() < BOOT_TIMEOUT {
+        if let Some(device) = find_running(&sdb, name) {
+            if device.state == "device" {
+                println!(
+                    "{} {} ({})",
+                    "Emulator ready".green().bold(),
+                    name.yellow(),
+                    &device.serial
+                );
+                return Ok(device);
+            }
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    Err(TizenError {
+        message: format!(
+            "Emulator {} did not connect to sdb in {} seconds",
+            name,
+            BOOT_TIMEOUT.as_secs()
+        ),
+    })
+}
+
+fn list(tizen_env: &TizenEnv) -> Result<i32, TizenError> {
+    let output = Command::new(&tizen_env.em_cli_bin)
+        .arg("list-vm")
+        .output()?;
+
+    if !output.status.success() {
+        return Err(TizenError {
+            message: format!(
+                "{} list-vm failed: {}",
+                &tizen_env.em_cli_bin,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        });
+    }
+
+    let names: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    if names.is_empty() {
+        println!(
+            "{}",
+            "No emulator created, use cargo tizen emulator create"
+                .yellow()
+                .bold()
+        );
+        return Ok(0);
+    }
+
+    let sdb = SdbClient::new(tizen_env);
+
+    let table = names
+        .iter()
+        .map(|name| {
+            let device = find_running(&sdb, name);
+
+            vec![
+                name.clone().cell(),
+                device
+                    .as_ref()
+                    .map(|device| device.serial.clone())
+                    .unwrap_or_default()
+                    .cell(),
+                device
+                    .map(|device| device.state)
+                    .unwrap_or_else(|| "stopped".to_string())
+                    .cell(),
+                match name == &tizen_env.emulator_name {
+                    true => "yes",
+                    false => "",
+                }
+                .cell(),
+            ]
+        })
+        .collect::<Vec<_>>()
+        .table()
+        .title(vec![
+            "name".cell(),
+            "serial".cell(),
+            "state".cell(),
+            "configured".cell(),
+        ]);
+
+    assert!(print_stdout(table).is_ok());
+
+    Ok(0)
+}
+
+// Returns false when the emulator is already running
+fn launch(tizen_env: &TizenEnv, name: &str) -> Result<bool, TizenError> {
+    let sdb = SdbClient::new(tizen_env);
+
+    if let Some(device) = find_running(&sdb, name) {
+        println!("Emulator {} is running ({})", name.yellow(), &device.serial);
+        return Ok(false);
+    }
+
+    println!(
+        "Running: {} {}",
+        &tizen_env.em_cli_bin.green().bold(),
+        format!("launch -n {}", name).green().bold()
+    );
+
+    let is_launched = Command::new(&tizen_env.em_cli_bin)
+        .args(["launch", "-n", name])
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false);
+
+    if !is_launched {
+        return Err(TizenError {
+            message: format!("Failed to launch the emulator {}", name),
+        });
+    }
+
+    Ok(true)
+}
+
+fn stop(tizen_env: &TizenEnv, name: &str) -> Result<i32, TizenError> {
+    let sdb = SdbClient::new(tizen_env);
+
+    let device = match find_running(&sdb, name) {
+        Some(device) => device,
+        None => {
+            println!("Emulator {} is not running", name.yellow());
+            return Ok(0);
+        }
+    };
+
+    println!(
+        "{} {} ({})",
+        "Stopping emulator".green().bold(),
+        name.yellow(),
+        &device.serial
+    );
+
+    sdb.root_on(&device.serial)?;
+
+    // The connection drops while the emulator powers off
+    if let Ok(mut stream) = sdb.open_shell(&device.serial, "poweroff") {
+        let _ = stream.read_to_end(&mut vec![]);
+    }
+
+    let started_at = Instant::now();
+
+    while started_at.elapsed() < STOP_TIMEOUT {
+        if find_running(&sdb, name).is_none() {
+            println!("{} {}", "Stopped".green().bold(), name.yellow());
+            return Ok(0);
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    Err(TizenError {
+        message: format!("Emulator {} is still running", name),
+    })
+}
+
+fn find_running(sdb: &SdbClient, name: &str) -> Option<SdbDevice> {
+    sdb.devices().ok().and_then(|devices| {
+        devices
+            .into_iter()
+            .find(|device| device.is_emulator() && device.name == name)
+    })
+}
+
+fn emulator_name(tizen_env: &TizenEnv, name: Option<&str>) -> Result<String, TizenError> {
+    match name {
+        Some(name) => Ok(name.to_string()),
+        None if !tizen_env.emulator_name.is_empty() => Ok(tizen_env.emulator_name.clone()),
+        None => Err(TizenError {
+            message: "Give the emulator name or set name in [tizen.emulator]".to_string(),
+        }),
+    }
+}
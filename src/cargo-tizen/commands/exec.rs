@@ -0,0 +1,27 @@
+use crate::error::TizenError;
+use crate::sdb::SdbClient;
+use crate::tizen_env::TizenEnv;
+use clap::ArgMatches;
+use std::path::Path;
+
+// Entry point of the cargo runner, prints only the binary output
+pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
+    let binary = Path::new(args.value_of("binary").unwrap_or(""));
+    let exec_args: Vec<String> = match args.values_of("exec_args") {
+        Some(values) => values.map(|v| v.to_string()).collect(),
+        None => vec![],
+    };
+
+    if !binary.is_file() {
+        return Err(TizenError {
+            message: format!("Binary not found {}", binary.to_str().unwrap_or("")),
+        });
+    }
+
+    let sdb = SdbClient::new(tizen_env);
+    let device = sdb.select_device()?;
+
+    sdb.exec(&device.serial, binary, &exec_args, &mut |line| {
+        println!("{}", line)
+    })
+}
@@ -0,0 +1,49 @@
+use crate::error::TizenError;
+use crate::gettext::extract_messages;
+use crate::tizen_env::TizenEnv;
+use clap::ArgMatches;
+use colored::*;
+use std::fs;
+use std::path::PathBuf;
+
+pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
+    match args.subcommand() {
+        ("extract", Some(extract_args)) => {
+            let output_path = match extract_args.value_of("output") {
+                Some(output) => tizen_env.base_path.join(output),
+                None => tizen_env
+                    .base_path
+                    .join(&tizen_env.po_dir)
+                    .join(format!("{}.pot", &tizen_env.text_domain)),
+            };
+
+            let (pot, count) = extract_messages(tizen_env)?;
+
+            if let Some(parent) = output_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            fs::write(&output_path, pot)?;
+
+            println!(
+                "{} {} messages to {}",
+                "Extracted".green().bold(),
+                count,
+                relative_display(tizen_env, output_path).yellow()
+            );
+
+            Ok(0)
+        }
+        _ => Err(TizenError {
+            message: "No command matched!".to_string(),
+        }),
+    }
+}
+
+fn relative_display(tizen_env: &TizenEnv, path: PathBuf) -> String {
+    path.strip_prefix(&tizen_env.base_path)
+        .unwrap_or(&path)
+        .to_str()
+        .unwrap_or("")
+        .to_string()
+}
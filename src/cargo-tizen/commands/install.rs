@@ -1,20 +1,96 @@
 use crate::error::TizenError;
 use crate::helpers::run_command;
+use crate::sdb::{shell_quote, SdbClient, REMOTE_TMP_DIR};
 use crate::tizen_env::TizenEnv;
 use clap::ArgMatches;
+use colored::*;
 
 pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
+    match tizen_env.device_tool.as_str() {
+        "sdb" => install_sdb(tizen_env),
+        "studio" => install_studio(tizen_env, args),
+        _ => Err(TizenError {
+            message: format!("Unsupported device tool {}", &tizen_env.device_tool),
+        }),
+    }
+}
+
+fn install_sdb(tizen_env: &TizenEnv) -> Result<i32, TizenError> {
+    let sdb = SdbClient::new(tizen_env);
+    let device = sdb.select_device()?;
+
+    let tpk_name = tizen_env.tpk_name();
+    let mut tpk_path = tizen_env.tizen_output_tpk_dir();
+    tpk_path.push(&tpk_name);
+
+    if !tpk_path.exists() {
+        return Err(TizenError {
+            message: format!(
+                "Package not found {}, run cargo tizen package first",
+                tpk_path.to_str().unwrap_or("")
+            ),
+        });
+    }
+
+    let remote_path = format!("{}/{}", REMOTE_TMP_DIR, &tpk_name);
+
+    println!(
+        "Pushing {} to {} ({})",
+        &tpk_name.yellow(),
+        &device.serial.yellow(),
+        &device.name
+    );
+
+    sdb.shell(
+        &device.serial,
+        &format!("mkdir -p {}", REMOTE_TMP_DIR),
+        &mut |_| {},
+    )?;
+    sdb.push(&device.serial, &tpk_path, &remote_path, 0o644)?;
+
+    let install_command = format!("pkgcmd -i -t tpk -p {}", shell_quote(&remote_path));
+    println!("Running: {}", &install_command.green().bold());
+
+    let mut has_failed = false;
+    let exit_code = sdb.shell(&device.serial, &install_command, &mut |line| {
+        println!("{}", line);
+        if line.contains("val[fail]") {
+            has_failed = true;
+        }
+    })?;
+
+    sdb.shell(
+        &device.serial,
+        &format!("rm -f {}", shell_quote(&remote_path)),
+        &mut |_| {},
+    )?;
+
+    if exit_code != 0 || has_failed {
+        return Err(TizenError {
+            message: "cargo tizen install failed!".to_string(),
+        });
+    }
+
+    Ok(0)
+}
+
+fn install_studio(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
     let tizen_output_tpk_dir = tizen_env.tizen_output_tpk_dir();
 
-    let tizen_args = vec![
+    let mut tizen_args = vec![
         "install".to_string(),
         "-n".to_string(),
         tizen_env.tpk_name(),
     ];
 
+    if !tizen_env.target_serial.is_empty() {
+        tizen_args.push("-s".to_string());
+        tizen_args.push(tizen_env.target_serial.clone());
+    }
+
     let mut handle = run_command(
-        &tizen_env,
-        &args,
+        tizen_env,
+        args,
         &tizen_env.tizen_bin,
         &tizen_args,
         None,
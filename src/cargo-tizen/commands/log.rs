@@ -0,0 +1,158 @@
+use crate::error::TizenError;
+use crate::sdb::{shell_quote, SdbClient};
+use crate::tizen_env::{TizenApplication, TizenEnv};
+use clap::ArgMatches;
+use colored::*;
+use std::io::{BufRead, BufReader};
+use std::net::Shutdown;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const PID_REFRESH: Duration = Duration::from_secs(2);
+
+pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
+    let application = tizen_env.find_application(args.value_of("app"))?;
+
+    stream(tizen_env, args, application)
+}
+
+pub fn stream(
+    tizen_env: &TizenEnv,
+    args: &ArgMatches,
+    application: &TizenApplication,
+) -> Result<i32, TizenError> {
+    let sdb = SdbClient::new(tizen_env);
+    let device = sdb.select_device()?;
+
+    let show_all = args.is_present("all");
+    let mut tags: Vec<String> = match args.values_of("tag") {
+        Some(values) => values.map(|v| v.to_string()).collect(),
+        None => vec![application.app_id.clone(), application.exec_name()],
+    };
+    tags.iter_mut().for_each(|tag| *tag = tag.to_lowercase());
+
+    let stream = sdb.open_shell(&device.serial, "dlogutil -v threadtime")?;
+    stream.set_read_timeout(None)?;
+
+    let is_stopped = Arc::new(AtomicBool::new(false));
+    let handler_stream = stream.try_clone()?;
+    let handler_stopped = is_stopped.clone();
+
+    if ctrlc::set_handler(move || {
+        handler_stopped.store(true, Ordering::SeqCst);
+        let _ = handler_stream.shutdown(Shutdown::Both);
+    })
+    .is_err()
+    {
+        return Err(TizenError {
+            message: "Can't listen for Ctrl-C".to_string(),
+        });
+    }
+
+    println!(
+        "{} {} {}",
+        "Streaming logs of".green().bold(),
+        &application.app_id.yellow(),
+        "(Ctrl-C to stop)".green().bold()
+    );
+
+    let mut pids: Vec<String> = vec![];
+    let mut pids_updated_at: Option<Instant> = None;
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) if is_stopped.load(Ordering::SeqCst) => break,
+            Err(error) => return Err(error.into()),
+        };
+
+        let entry = match LogEntry::parse(line.trim_end_matches('\r')) {
+            Some(entry) => entry,
+            None => continue,
+        };
+
+        if !show_all {
+            let is_outdated = match pids_updated_at {
+                Some(updated_at) => updated_at.elapsed() > PID_REFRESH,
+                None => true,
+            };
+
+            if is_outdated {
+                pids = find_pids(&sdb, &device.serial, &application.exec_name());
+                pids_updated_at = Some(Instant::now());
+            }
+
+            let is_app_entry = pids.iter().any(|pid| pid == entry.pid)
+                || tags.iter().any(|tag| tag == &entry.tag.to_lowercase());
+
+            if !is_app_entry {
+                continue;
+            }
+        }
+
+        println!("{}", entry.colorize(&line));
+    }
+
+    println!("{}", "Stopped".green().bold());
+
+    Ok(0)
+}
+
+fn find_pids(sdb: &SdbClient, serial: &str, exec_name: &str) -> Vec<String> {
+    let mut pids: Vec<String> = vec![];
+
+    let _ = sdb.shell(
+        serial,
+        &format!("pidof {}", shell_quote(exec_name)),
+        &mut |line| pids.extend(line.split_whitespace().map(|pid| pid.to_string())),
+    );
+
+    pids
+}
+
+// dlogutil -v threadtime: "10-17 12:00:00.123+0900 I/TAG ( P 1234, T 1235): message"
+struct LogEntry<'l> {
+    priority: char,
+    tag: &'l str,
+    pid: &'l str,
+}
+
+impl<'l> LogEntry<'l> {
+    fn parse(line: &'l str) -> Option<Self> {
+        let slash = line.find(|c| "VDIWEF".contains(c)).and_then(|start| {
+            line[start..]
+                .find('/')
+                .map(|pos| start + pos)
+                .filter(|pos| *pos == start + 1)
+        })?;
+
+        let priority = line[..slash].chars().last()?;
+        let open = slash + line[slash..].find('(')?;
+        let tag = line[slash + 1..open].trim();
+
+        let pid_start = line[open + 1..]
+            .find(|c: char| c.is_ascii_digit())
+            .map(|pos| open + 1 + pos)?;
+        let pid_len = line[pid_start..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(line.len() - pid_start);
+
+        Some(Self {
+            priority,
+            tag,
+            pid: &line[pid_start..pid_start + pid_len],
+        })
+    }
+
+    fn colorize(&self, line: &str) -> ColoredString {
+        match self.priority {
+            'V' | 'D' => line.dimmed(),
+            'I' => line.green(),
+            'W' => line.yellow(),
+            'E' => line.red(),
+            'F' => line.red().bold(),
+            _ => line.normal(),
+        }
+    }
+}
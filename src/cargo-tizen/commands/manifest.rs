@@ -0,0 +1,223 @@
+use crate::error::TizenError;
+use crate::manifest::{escape_xml, is_valid_package_id, is_valid_version, ManifestEditor};
+use crate::tizen_env::{manifest_key, ConfigType, TizenEnv};
+use clap::ArgMatches;
+use colored::*;
+
+const PRIVILEGES_XPATH: &str = "/ns:manifest/ns:privileges";
+
+pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
+    if tizen_env.generated_manifest.is_some() {
+        return Err(TizenError {
+            message: "tizen-manifest.xml is generated from [package.metadata.tizen], edit Cargo.toml instead".to_string(),
+        });
+    }
+
+    let mut editor = ManifestEditor::open(&tizen_env.base_path)?;
+
+    match args.subcommand() {
+        ("privilege", Some(privilege_args)) => match privilege_args.subcommand() {
+            ("add", Some(add_args)) => {
+                for uri in add_args.values_of("uri").unwrap_or_default() {
+                    add_privilege(&mut editor, uri)?;
+                }
+            }
+            ("remove", Some(remove_args)) => {
+                for uri in remove_args.values_of("uri").unwrap_or_default() {
+                    remove_privilege(&mut editor, uri)?;
+                }
+            }
+            _ => return Err(no_command_matched()),
+        },
+        ("feature", Some(feature_args)) => match feature_args.subcommand() {
+            ("add", Some(add_args)) => add_feature(
+                &mut editor,
+                add_args.value_of("name").unwrap_or(""),
+                add_args.value_of("value").unwrap_or("true"),
+            )?,
+            ("remove", Some(remove_args)) => {
+                remove_feature(&mut editor, remove_args.value_of("name").unwrap_or(""))?
+            }
+            _ => return Err(no_command_matched()),
+        },
+        ("set", Some(set_args)) => set_value(
+            &mut editor,
+            set_args.value_of("key").unwrap_or(""),
+            set_args.value_of("value").unwrap_or(""),
+            set_args.value_of("app"),
+        )?,
+        _ => return Err(no_command_matched()),
+    }
+
+    editor.save()?;
+    println!(
+        "{} {}",
+        "Updated".green().bold(),
+        "tizen-manifest.xml".yellow()
+    );
+
+    Ok(0)
+}
+
+fn add_privilege(editor: &mut ManifestEditor, uri: &str) -> Result<(), TizenError> {
+    if editor.exists(&privilege_xpath(uri)?)? {
+        println!("Privilege {} already declared", uri.yellow());
+        return Ok(());
+    }
+
+    if !editor.exists(PRIVILEGES_XPATH)? {
+        editor.add_child("/ns:manifest", "<privileges></privileges>")?;
+    }
+
+    editor.add_child(
+        PRIVILEGES_XPATH,
+        &format!("<privilege>{}</privilege>", escape_xml(uri)),
+    )?;
+    println!("Added privilege {}", uri.yellow());
+
+    Ok(())
+}
+
+fn remove_privilege(editor: &mut ManifestEditor, uri: &str) -> Result<(), TizenError> {
+    let xpath = privilege_xpath(uri)?;
+
+    if !editor.exists(&xpath)? {
+        println!("Privilege {} is not declared", uri.yellow());
+        return Ok(());
+    }
+
+    editor.remove_element(&xpath)?;
+    println!("Removed privilege {}", uri.yellow());
+
+    if !editor.exists(&format!("{}/ns:privilege", PRIVILEGES_XPATH))? {
+        editor.remove_element(PRIVILEGES_XPATH)?;
+    }
+
+    Ok(())
+}
+
+fn add_feature(editor: &mut ManifestEditor, name: &str, value: &str) -> Result<(), TizenError> {
+    let xpath = feature_xpath(name)?;
+
+    if editor.exists(&xpath)? {
+        editor.set_value(&xpath, value)?;
+        println!("Updated feature {} = {}", name.yellow(), value.yellow());
+        return Ok(());
+    }
+
+    editor.add_child(
+        "/ns:manifest",
+        &format!(
+            "<feature name=\"{}\">{}</feature>",
+            escape_xml(name),
+            escape_xml(value)
+        ),
+    )?;
+    println!("Added feature {} = {}", name.yellow(), value.yellow());
+
+    Ok(())
+}
+
+fn remove_feature(editor: &mut ManifestEditor, name: &str) -> Result<(), TizenError> {
+    let xpath = feature_xpath(name)?;
+
+    if !editor.exists(&xpath)? {
+        println!("Feature {} is not declared", name.yellow());
+        return Ok(());
+    }
+
+    editor.remove_element(&xpath)?;
+    println!("Removed feature {}", name.yellow());
+
+    Ok(())
+}
+
+fn set_value(
+    editor: &mut ManifestEditor,
+    key: &str,
+    value: &str,
+    app_id: Option<&str>,
+) -> Result<(), TizenError> {
+    let config_type = match key {
+        "version" => ConfigType::AppVersion,
+        "api-version" => ConfigType::ApiVersion,
+        "package" => ConfigType::AppPackage,
+        "profile" => ConfigType::AppProfile,
+        "appid" => ConfigType::AppId,
+        "exec" => ConfigType::AppExec,
+        "label" => ConfigType::AppLabel,
+        "type" => ConfigType::AppUiType,
+        _ => {
+            return Err(TizenError {
+                message: format!("Unknown manifest key {}", key),
+            })
+        }
+    };
+
+    let is_valid = match config_type {
+        ConfigType::AppVersion => is_valid_version(value),
+        ConfigType::AppPackage => is_valid_package_id(value),
+        ConfigType::ApiVersion => {
+            !value.is_empty() && value.split('.').all(|p| p.parse::<u32>().is_ok())
+        }
+        _ => !value.is_empty(),
+    };
+
+    if !is_valid {
+        return Err(TizenError {
+            message: format!("Invalid value '{}' for {}", value, key),
+        });
+    }
+
+    let mut xpath = match manifest_key(&config_type) {
+        Some(xpath) => xpath,
+        None => {
+            return Err(TizenError {
+                message: format!("No manifest key for {}", key),
+            })
+        }
+    };
+
+    if let Some(app_id) = app_id {
+        xpath = xpath.replace(
+            "(/ns:manifest/*[@appid])[1]",
+            &format!("/ns:manifest/*[@appid={}]", xpath_literal(app_id)?),
+        );
+    }
+
+    editor.set_value(&xpath, value)?;
+    println!("Set {} = {}", key.yellow(), value.yellow());
+
+    Ok(())
+}
+
+fn privilege_xpath(uri: &str) -> Result<String, TizenError> {
+    Ok(format!(
+        "{}/ns:privilege[normalize-space(.)={}]",
+        PRIVILEGES_XPATH,
+        xpath_literal(uri)?
+    ))
+}
+
+fn feature_xpath(name: &str) -> Result<String, TizenError> {
+    Ok(format!(
+        "/ns:manifest/ns:feature[@name={}]",
+        xpath_literal(name)?
+    ))
+}
+
+fn xpath_literal(value: &str) -> Result<String, TizenError> {
+    match (value.contains('\''), value.contains('"')) {
+        (false, _) => Ok(format!("'{}'", value)),
+        (true, false) => Ok(format!("\"{}\"", value)),
+        (true, true) => Err(TizenError {
+            message: format!("Unsupported value {}", value),
+        }),
+    }
+}
+
+fn no_command_matched() -> TizenError {
+    TizenError {
+        message: "No command matched!".to_string(),
+    }
+}
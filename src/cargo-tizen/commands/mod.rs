@@ -1,7 +1,18 @@
 pub mod build;
+pub mod check;
 pub mod clean;
 pub mod config;
+pub mod debug;
 pub mod dev;
+pub mod devices;
+pub mod emulator;
+pub mod exec;
+pub mod i18n;
 pub mod install;
+pub mod log;
+pub mod manifest;
+pub mod new;
 pub mod package;
+pub mod profiles;
 pub mod run;
+pub mod test;
@@ -0,0 +1,177 @@
+use crate::error::TizenError;
+use crate::manifest::{tizen_version, ManifestValues};
+use crate::workspace::read_cargo_toml;
+use clap::ArgMatches;
+use colored::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const CARGO_CONFIG: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/res/templates/cargo-config.toml"
+));
+
+const ICON: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/res/templates/icon.png"
+));
+
+pub fn run(args: &ArgMatches, is_init: bool) -> Result<i32, TizenError> {
+    let project_path = PathBuf::from(args.value_of("path").unwrap_or("."));
+
+    let mut cargo_toml_path = project_path.clone();
+    cargo_toml_path.push("Cargo.toml");
+
+    if is_init && cargo_toml_path.exists() {
+        println!(
+            "Using existing {}",
+            cargo_toml_path.to_str().unwrap_or("").yellow()
+        );
+    } else {
+        run_cargo(args, &project_path, is_init)?;
+    }
+
+    let cargo_toml = read_cargo_toml(&project_path)?;
+
+    let cargo_pkg_name = match cargo_toml
+        .get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+    {
+        Some(name) => name.to_string(),
+        None => {
+            return Err(TizenError {
+                message: "Can't get package.name from Cargo.toml".to_string(),
+            })
+        }
+    };
+
+    let package_id = match args.value_of("package_id") {
+        Some(package_id) => package_id.to_string(),
+        None => format!("org.example.{}", cargo_pkg_name.replace('-', "_")),
+    };
+    let app_id = args.value_of("app_id").unwrap_or(&package_id).to_string();
+    let icon_name = format!("{}.png", &cargo_pkg_name);
+
+    // Starts in sync with the Cargo.toml version, a workspace inherited one is not resolved
+    let version = cargo_toml
+        .get("package")
+        .and_then(|p| p.get("version"))
+        .and_then(|v| v.as_str())
+        .and_then(|v| tizen_version(v).ok())
+        .unwrap_or_else(|| "1.0.0".to_string());
+
+    let manifest = ManifestValues {
+        app_type: args.value_of("template").unwrap_or("ui").to_string(),
+        api_version: args.value_of("api_version").unwrap_or("5.5").to_string(),
+        profile: args.value_of("profile").unwrap_or("wearable").to_string(),
+        package: package_id,
+        version,
+        app_id,
+        exec: cargo_pkg_name.clone(),
+        label: cargo_pkg_name.clone(),
+        icon: Some(icon_name.clone()),
+        privileges: args
+            .values_of("privilege")
+            .map(|values| values.map(|v| v.to_string()).collect())
+            .unwrap_or_default(),
+        features: vec![],
+    }
+    .render()?;
+
+    write_new_file(&project_path, "tizen-manifest.xml", manifest.as_bytes())?;
+    write_new_file(&project_path, &format!("shared/res/{}", &icon_name), ICON)?;
+    write_new_file(&project_path, "res/.gitkeep", b"")?;
+
+    let cargo_config = CARGO_CONFIG.replace("{{studio_path}}", &default_studio_path(args));
+
+    write_cargo_config(&project_path, &cargo_config)?;
+
+    println!(
+        "{} {}",
+        "Created tizen project".green().bold(),
+        project_path.to_str().unwrap_or("").yellow()
+    );
+
+    Ok(0)
+}
+
+fn run_cargo(args: &ArgMatches, project_path: &Path, is_init: bool) -> Result<(), TizenError> {
+    let mut cargo_args: Vec<String> = vec![
+        if is_init { "init" } else { "new" }.to_string(),
+        "--bin".to_string(),
+        project_path.to_str().unwrap_or(".").to_string(),
+    ];
+
+    if let Some(name) = args.value_of("name") {
+        cargo_args.push(format!("--name={}", name));
+    }
+
+    println!(
+        "Running: {} {}",
+        "cargo".green().bold(),
+        cargo_args.join(" ").green().bold()
+    );
+
+    match Command::new("cargo").args(&cargo_args).status() {
+        Ok(exit_code) if exit_code.success() => Ok(()),
+        _ => Err(TizenError {
+            message: format!("cargo {} failed!", &cargo_args[0]),
+        }),
+    }
+}
+
+fn default_studio_path(args: &ArgMatches) -> String {
+    match args.value_of("studio_path") {
+        Some(studio_path) => studio_path.to_string(),
+        None => match std::env::var("HOME") {
+            Ok(home) => format!("{}/tizen-studio", home),
+            Err(_) => "".to_string(),
+        },
+    }
+}
+
+fn write_new_file(project_path: &Path, name: &str, content: &[u8]) -> Result<(), TizenError> {
+    let mut file_path = PathBuf::from(project_path);
+    file_path.push(name);
+
+    if file_path.exists() {
+        println!("Skip existing {}", name.yellow());
+        return Ok(());
+    }
+
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(&file_path, content)?;
+    println!("Created {}", name.yellow());
+
+    Ok(())
+}
+
+fn write_cargo_config(project_path: &Path, cargo_config: &str) -> Result<(), TizenError> {
+    let mut config_path = PathBuf::from(project_path);
+    config_path.push(".cargo");
+    config_path.push("config.toml");
+
+    if !config_path.exists() {
+        return write_new_file(project_path, ".cargo/config.toml", cargo_config.as_bytes());
+    }
+
+    let current_config = fs::read_to_string(&config_path)?;
+
+    if current_config.lines().any(|line| line.trim() == "[tizen]") {
+        println!("Skip existing {}", ".cargo/config.toml".yellow());
+        return Ok(());
+    }
+
+    fs::write(
+        &config_path,
+        format!("{}\n{}", current_config, cargo_config),
+    )?;
+    println!("Updated {}", ".cargo/config.toml".yellow());
+
+    Ok(())
+}
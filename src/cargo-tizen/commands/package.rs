@@ -1,6 +1,16 @@
+use crate::commands;
+use crate::commands::build::{select_executable, BuiltExecutable};
+use crate::edje::compile_edcs;
 use crate::error::TizenError;
-use crate::helpers::{ask, run_command};
-use crate::tizen_env::TizenEnv;
+use crate::gettext::compile_catalogs;
+use crate::helpers::run_command;
+use crate::hooks::{run_hook, Hook};
+use crate::manifest::{check_manifest, ManifestEditor};
+use crate::profiles::find_profile;
+use crate::signer::{sign_package, SigningCert, AUTHOR_SIGNATURE, DISTRIBUTOR_SIGNATURE};
+use crate::staging::{copy_mtime, Staging};
+use crate::tizen_env::{manifest_key, ApplicationType, ConfigType, TizenEnv};
+use crate::tpk::{write_tpk, TPK_DIRS};
 use clap::ArgMatches;
 use colored::*;
 use std::fs;
@@ -11,13 +21,107 @@ use std::path::PathBuf;
 use std::process::Command;
 
 pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
-    let assume_yes = args.is_present("assumeyes");
+    let executables = commands::build::build(tizen_env, args)?;
+
+    package(tizen_env, args, &executables)
+}
+
+pub fn package(
+    tizen_env: &TizenEnv,
+    args: &ArgMatches,
+    executables: &[BuiltExecutable],
+) -> Result<i32, TizenError> {
+    let mut bins: Vec<(PathBuf, String)> = vec![];
+    for application in tizen_env.applications.iter() {
+        if let ApplicationType::Unsupported(_) = application.app_type {
+            return Err(TizenError {
+                message: format!("Unsupported app type {}", application.app_type.element()),
+            });
+        }
+
+        let executable = select_executable(tizen_env, args, executables, application)?;
+
+        if !bins
+            .iter()
+            .any(|(_, name)| name == &application.exec_name())
+        {
+            bins.push((executable.path.clone(), application.exec_name()));
+        }
+    }
+
+    let bin_names: Vec<String> = executables.iter().map(|e| e.name.clone()).collect();
+    commands::check::report(tizen_env, &check_manifest(tizen_env, Some(&bin_names))?)?;
+
+    let exit_code = match tizen_env.packager.as_str() {
+        "native" => package_native(tizen_env, &bins),
+        "studio" => package_studio(tizen_env, args, &bins),
+        _ => Err(TizenError {
+            message: format!("Unsupported packager {}", &tizen_env.packager),
+        }),
+    }?;
+
+    run_hook(tizen_env, Hook::PostPackage)?;
+
+    Ok(exit_code)
+}
+
+fn package_native(tizen_env: &TizenEnv, bins: &[(PathBuf, String)]) -> Result<i32, TizenError> {
+    let tizen_output_dir = tizen_env.tizen_output_dir();
+    let tizen_output_tpk_dir = tizen_env.tizen_output_tpk_dir();
+
+    let mut bin_dir = tizen_output_dir.clone();
+    bin_dir.push("bin");
+
+    create_tizen_output(tizen_env, bins, &bin_dir, &TPK_DIRS)?;
+
+    fs::create_dir_all(&tizen_output_tpk_dir)?;
+
+    let excludes = [tizen_output_tpk_dir.clone()];
+
+    match signing_certs(tizen_env)? {
+        Some((author, distributor)) => {
+            sign_package(&tizen_output_dir, &excludes, &author, &distributor)?;
+
+            println!("Created {}", AUTHOR_SIGNATURE.yellow());
+            println!("Created {}", DISTRIBUTOR_SIGNATURE.yellow());
+        }
+        None => println!(
+            "{}",
+            "No author certificate or security profile configured, the package will be unsigned"
+                .bold()
+                .yellow()
+        ),
+    }
+
+    let tpk_name = tizen_env.tpk_name();
+    let mut tpk_path = tizen_output_tpk_dir.clone();
+    tpk_path.push(&tpk_name);
+
+    write_tpk(&tizen_output_dir, &tpk_path, &excludes)?;
+
+    println!("Created {}", &tpk_name.yellow());
+
+    Ok(0)
+}
+
+fn package_studio(
+    tizen_env: &TizenEnv,
+    args: &ArgMatches,
+    bins: &[(PathBuf, String)],
+) -> Result<i32, TizenError> {
+    if has_security_profile(tizen_env) {
+        find_profile(&tizen_env.studio_path, &tizen_env.security_profile)?;
+    }
 
     let tizen_output_dir = tizen_env.tizen_output_dir();
     let tizen_output_tpk_dir = tizen_env.tizen_output_tpk_dir();
 
-    remove_tizen_output_if_exists(&tizen_output_dir, assume_yes)?;
-    create_tizen_output(&tizen_env)?;
+    create_tizen_output(tizen_env, bins, &tizen_output_tpk_dir, &[])?;
+
+    create_build_info(tizen_env, &tizen_output_dir, &tizen_output_tpk_dir)?;
+
+    create_project_def(tizen_env, &tizen_output_dir)?;
+    create_project_xml(tizen_env, &tizen_output_dir)?;
 
     let mut tizen_args = vec![
         "package".to_string(),
@@ -27,14 +131,14 @@ pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
         tizen_output_dir.to_str().unwrap().to_string(),
     ];
 
-    if !tizen_env.security_profile.is_empty() && tizen_env.security_profile != "default" {
+    if has_security_profile(tizen_env) {
         tizen_args.push("--sign".to_string());
         tizen_args.push(tizen_env.security_profile.clone());
     }
 
     let mut handle = run_command(
-        &tizen_env,
-        &args,
+        tizen_env,
+        args,
         &tizen_env.tizen_bin,
         &tizen_args,
         None,
@@ -53,109 +157,216 @@ pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
     Ok(exit_code.code().unwrap())
 }
 
-fn remove_tizen_output_if_exists(
-    tizen_output_dir: &Path,
-    assume_yes: bool,
-) -> Result<(), TizenError> {
-    if tizen_output_dir.exists() {
-        println!(
-            "The folder {} already exist",
-            &tizen_output_dir.to_str().unwrap().yellow()
-        );
-
-        if assume_yes || ask("The folder can be deleted?") {
-            fs::remove_dir_all(&tizen_output_dir)?;
-            println!("{}", "Folder removed!".green());
-            Ok(())
-        } else {
-            Err(TizenError {
-                message: "Operation was cancelled".to_string(),
-            })
-        }
-    } else {
-        Ok(())
-    }
+fn has_security_profile(tizen_env: &TizenEnv) -> bool {
+    !tizen_env.security_profile.is_empty() && tizen_env.security_profile != "default"
 }
 
-fn create_tizen_output(tizen_env: &TizenEnv) -> Result<(), TizenError> {
-    let tizen_output_dir = tizen_env.tizen_output_dir();
-    let tizen_output_tpk_dir = tizen_env.tizen_output_tpk_dir();
-    let rust_output_dir = tizen_env.rust_output_dir();
+fn signing_certs(tizen_env: &TizenEnv) -> Result<Option<(SigningCert, SigningCert)>, TizenError> {
+    let distributor_from_config = || {
+        SigningCert::from_p12(
+            &tizen_env.base_path.join(&tizen_env.distributor_cert),
+            &tizen_env.distributor_password,
+        )
+    };
+
+    if !tizen_env.author_cert.is_empty() {
+        let author = SigningCert::from_p12(
+            &tizen_env.base_path.join(&tizen_env.author_cert),
+            &tizen_env.author_password,
+        )?;
 
-    fs::create_dir(&tizen_output_dir)?;
+        return Ok(Some((author, distributor_from_config()?)));
+    }
 
-    let mut old_bin = PathBuf::from(&rust_output_dir);
-    old_bin.push(&tizen_env.cargo_pkg_name);
+    if !has_security_profile(tizen_env) {
+        return Ok(None);
+    }
 
-    if !old_bin.exists() {
+    let profile = find_profile(&tizen_env.studio_path, &tizen_env.security_profile)?;
+
+    let author_password = if !tizen_env.author_password.is_empty() {
+        tizen_env.author_password.clone()
+    } else if profile.author.has_plain_password() {
+        profile.author.password.clone()
+    } else {
         return Err(TizenError {
             message: format!(
-                "Cargo generated bin dont exists: {}",
-                &old_bin.to_str().unwrap()
+                "Security profile '{}' keeps the author password encrypted, set tizen.sign.author_password",
+                &profile.name
             ),
         });
+    };
+
+    let author = SigningCert::from_p12(Path::new(&profile.author.key), &author_password)?;
+
+    // The encrypted distributor password falls back to tizen.sign.distributor_password,
+    // the password of the default Tizen distributor certificates
+    let distributor = if profile.distributor.key.is_empty() {
+        distributor_from_config()?
+    } else if profile.distributor.has_plain_password() {
+        SigningCert::from_p12(
+            Path::new(&profile.distributor.key),
+            &profile.distributor.password,
+        )?
+    } else {
+        SigningCert::from_p12(
+            Path::new(&profile.distributor.key),
+            &tizen_env.distributor_password,
+        )?
+    };
+
+    Ok(Some((author, distributor)))
+}
+
+// Syncs the staging folder, only the changed files are copied and the files
+// that are no longer packaged are removed
+fn create_tizen_output(
+    tizen_env: &TizenEnv,
+    bins: &[(PathBuf, String)],
+    bin_dir: &Path,
+    empty_dirs: &[&str],
+) -> Result<(), TizenError> {
+    let tizen_output_dir = tizen_env.tizen_output_dir();
+
+    for (old_bin, _) in bins.iter() {
+        if !old_bin.exists() {
+            return Err(TizenError {
+                message: format!(
+                    "Cargo generated bin dont exists: {}",
+                    &old_bin.to_str().unwrap()
+                ),
+            });
+        }
     }
 
-    for sync_file in tizen_env.sync_files.iter() {
-        let mut old_file_path = PathBuf::from(&tizen_env.base_path);
-        old_file_path.push(sync_file);
+    let mut staging = Staging::new(&tizen_output_dir);
+    staging.keep(Path::new("out"));
 
-        let mut new_file_path = PathBuf::from(&tizen_output_dir);
-        new_file_path.push(&sync_file);
+    // The signatures are written again after the sync, or not at all when unsigned
+    for signature_file in [AUTHOR_SIGNATURE, DISTRIBUTOR_SIGNATURE].iter() {
+        staging.keep(Path::new(signature_file));
 
-        if old_file_path.exists() {
-            if old_file_path.is_file() {
-                println!("Sync {}", &sync_file.yellow());
-                fs::copy(&old_file_path, &new_file_path)?;
-            } else if old_file_path.is_dir() {
-                println!("Sync {}", &sync_file.yellow());
-                copy_recursive(&old_file_path, &new_file_path)?;
-            }
+        let signature_path = tizen_output_dir.join(signature_file);
+        if signature_path.exists() {
+            fs::remove_file(&signature_path)?;
         }
+    }
 
-        // @TODO Copy directory
+    for empty_dir in empty_dirs.iter() {
+        staging.add_empty_dir(Path::new(empty_dir));
     }
 
-    fs::create_dir(&tizen_output_tpk_dir)?;
+    for synced_file in tizen_env.synced_files()?.iter() {
+        staging.add_file(&synced_file.dest, &synced_file.source);
+    }
 
-    let mut new_bin = tizen_output_tpk_dir.clone();
-    new_bin.push(&tizen_env.cargo_pkg_name);
+    for compiled_edc in compile_edcs(tizen_env)?.iter() {
+        staging.add_file(&compiled_edc.dest, &compiled_edc.edj_path);
+    }
+
+    for catalog in compile_catalogs(tizen_env)?.iter() {
+        staging.add_content(&catalog.dest, &catalog.content);
+    }
 
-    fs::copy(&old_bin, &new_bin)?;
+    if let Some(manifest_content) = staged_manifest(tizen_env)? {
+        staging.add_content(Path::new("tizen-manifest.xml"), manifest_content.as_bytes());
+    }
+
+    let relative_bin_dir = bin_dir.strip_prefix(&tizen_output_dir).unwrap_or(bin_dir);
+
+    for (old_bin, bin_name) in bins.iter() {
+        staging.add_file(&relative_bin_dir.join(bin_name), old_bin);
+    }
+
+    let report = staging.sync()?;
+
+    for written in report.written.iter() {
+        println!("Sync {}", written.to_str().unwrap_or("").yellow());
+    }
+
+    println!(
+        "Staging synced, {} updated, {} unchanged, {} removed",
+        report.written.len(),
+        report.unchanged,
+        report.removed
+    );
 
     if tizen_env.is_release {
-        if let Some(strip_bin) = tizen_env.strip_bin() {
-            let strip_args = ["--strip-debug", new_bin.to_str().unwrap()];
-            println!(
-                "Running {} {}",
-                strip_bin.green().bold(),
-                strip_args.join(" ").green().bold()
-            );
-
-            match Command::new(strip_bin).args(&strip_args).spawn() {
-                Ok(mut handle) => match handle.wait() {
-                    Ok(exit) => {
-                        if !exit.success() {
-                            println!("{}", "Can't strip bin".bold().red());
-                        }
-                    }
-                    Err(_) => println!("{}", "Can't strip bin".bold().red()),
-                },
-                Err(_) => println!("{}", "Can't strip bin".bold().red()),
+        for (old_bin, bin_name) in bins.iter() {
+            let relative_bin = relative_bin_dir.join(bin_name);
+
+            if report.written.contains(&relative_bin) {
+                let new_bin = tizen_output_dir.join(&relative_bin);
+                strip_bin(tizen_env, &new_bin);
+                // The stripped bin keeps the mtime of the cargo bin to be seen as unchanged
+                copy_mtime(old_bin, &new_bin)?;
             }
-        } else {
-            println!("{}", "Strip tool not found!".bold().yellow());
         }
     }
 
-    create_build_info(&tizen_env, &tizen_output_dir, &tizen_output_tpk_dir)?;
-
-    create_project_def(&tizen_env, &tizen_output_dir)?;
-    create_project_xml(&tizen_env, &tizen_output_dir)?;
+    // Runs on the synced staging folder, before it is signed and zipped
+    run_hook(tizen_env, Hook::PrePackage)?;
 
     Ok(())
 }
 
+// The generated manifest, or the manifest with the Cargo.toml version, when it
+// differs from tizen-manifest.xml
+fn staged_manifest(tizen_env: &TizenEnv) -> Result<Option<String>, TizenError> {
+    let is_rewritten = tizen_env.package_version() != tizen_env.app_version;
+
+    if tizen_env.generated_manifest.is_none() && !is_rewritten {
+        return Ok(None);
+    }
+
+    if tizen_env.generated_manifest.is_some() {
+        println!("Generated {}", "tizen-manifest.xml".yellow());
+    }
+
+    let mut editor =
+        ManifestEditor::from_content(&tizen_env.base_path, &tizen_env.manifest_content()?);
+
+    if is_rewritten {
+        editor.set_value(
+            &manifest_key(&ConfigType::AppVersion).unwrap_or_default(),
+            &tizen_env.package_version(),
+        )?;
+
+        println!(
+            "Set version {} in the packaged {}",
+            tizen_env.package_version().yellow(),
+            "tizen-manifest.xml".yellow()
+        );
+    }
+
+    Ok(Some(editor.into_content()?))
+}
+
+fn strip_bin(tizen_env: &TizenEnv, bin_path: &Path) {
+    if let Some(strip_bin) = tizen_env.strip_bin() {
+        let strip_args = ["--strip-debug", bin_path.to_str().unwrap()];
+        println!(
+            "Running {} {}",
+            strip_bin.green().bold(),
+            strip_args.join(" ").green().bold()
+        );
+
+        match Command::new(strip_bin).args(strip_args).spawn() {
+            Ok(mut handle) => match handle.wait() {
+                Ok(exit) => {
+                    if !exit.success() {
+                        println!("{}", "Can't strip bin".bold().red());
+                    }
+                }
+                Err(_) => println!("{}", "Can't strip bin".bold().red()),
+            },
+            Err(_) => println!("{}", "Can't strip bin".bold().red()),
+        }
+    } else {
+        println!("{}", "Strip tool not found!".bold().yellow());
+    }
+}
+
 fn create_build_info(
     tizen_env: &TizenEnv,
     tizen_output_dir: &Path,
@@ -163,7 +374,7 @@ fn create_build_info(
 ) -> Result<(), TizenError> {
     let file_name = "build.info";
     let mut build_info_path = PathBuf::from(&tizen_output_bin_dir);
-    build_info_path.push(&file_name);
+    build_info_path.push(file_name);
 
     let mut build_info_file = File::create(build_info_path)?;
 
@@ -198,14 +409,21 @@ fn create_project_def(tizen_env: &TizenEnv, tizen_output_dir: &Path) -> Result<(
     file_path.push(file_name);
 
     let mut file = File::create(file_path)?;
-    let app_type = match tizen_env.app_ui_type.as_str() {
-        "capp" => "app",
-        _ => {
-            return Err(TizenError {
-                message: format!("Unsupported app type {}", &tizen_env.app_ui_type),
-            })
-        }
-    };
+    // Studio builds the ui, service, widget and watch applications of a native
+    // package as one "app" project, only other languages are rejected
+    if let Some(application) = tizen_env
+        .applications
+        .iter()
+        .find(|application| application.ui_type != "capp")
+    {
+        return Err(TizenError {
+            message: format!(
+                "Unsupported app type {} for {}",
+                &application.ui_type, &application.app_id
+            ),
+        });
+    }
+    let app_type = "app";
 
     writeln!(file, "APPNAME = {}", &tizen_env.app_label)?;
     writeln!(file, "type = {}", &app_type)?;
@@ -229,7 +447,7 @@ fn create_project_def(tizen_env: &TizenEnv, tizen_output_dir: &Path) -> Result<(
 fn create_project_xml(tizen_env: &TizenEnv, tizen_output_dir: &Path) -> Result<(), TizenError> {
     let file_name = ".project";
     let mut file_path = PathBuf::from(&tizen_output_dir);
-    file_path.push(&file_name);
+    file_path.push(file_name);
 
     let mut file = File::create(file_path)?;
 
@@ -246,48 +464,3 @@ fn create_project_xml(tizen_env: &TizenEnv, tizen_output_dir: &Path) -> Result<(
 
     Ok(())
 }
-
-pub fn copy_recursive<U: AsRef<Path>, V: AsRef<Path>>(
-    from: U,
-    to: V,
-) -> Result<(), std::io::Error> {
-    let mut stack: Vec<PathBuf> = vec![PathBuf::from(from.as_ref())];
-
-    let output_root = PathBuf::from(to.as_ref());
-    let input_root = PathBuf::from(from.as_ref()).components().count();
-
-    while let Some(working_path) = stack.pop() {
-        // Generate a relative path
-        let src: PathBuf = working_path.components().skip(input_root).collect();
-
-        // Create a destination if missing
-        let dest = if src.components().count() == 0 {
-            output_root.clone()
-        } else {
-            output_root.join(&src)
-        };
-        if fs::metadata(&dest).is_err() {
-            fs::create_dir_all(&dest)?;
-        }
-
-        for entry in fs::read_dir(working_path)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_dir() {
-                stack.push(path);
-            } else {
-                match path.file_name() {
-                    Some(filename) => {
-                        let dest_path = dest.join(filename);
-                        fs::copy(&path, &dest_path)?;
-                    }
-                    None => {
-                        println!("failed: {:?}", path);
-                    }
-                }
-            }
-        }
-    }
-
-    Ok(())
-}
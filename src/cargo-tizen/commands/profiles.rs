@@ -0,0 +1,58 @@
+use crate::error::TizenError;
+use crate::profiles::{find_profile, profiles_path, read_profiles};
+use crate::tizen_env::TizenEnv;
+use clap::ArgMatches;
+use cli_table::{print_stdout, Cell, Table};
+use colored::*;
+
+pub fn run(tizen_env: &TizenEnv, _args: &ArgMatches) -> Result<i32, TizenError> {
+    let profiles = read_profiles(&tizen_env.studio_path)?;
+
+    println!(
+        "{} {}",
+        "Security profiles from".green().bold(),
+        profiles_path(&tizen_env.studio_path)
+            .to_str()
+            .unwrap_or("")
+            .yellow()
+    );
+
+    let table = profiles
+        .iter()
+        .map(|profile| {
+            vec![
+                profile.name.clone().cell(),
+                yes_or_empty(profile.is_active).cell(),
+                yes_or_empty(profile.name == tizen_env.security_profile).cell(),
+                profile.author.key.clone().cell(),
+                profile.author.ca.clone().cell(),
+                profile.distributor.key.clone().cell(),
+            ]
+        })
+        .collect::<Vec<_>>()
+        .table()
+        .title(vec![
+            "name".cell(),
+            "active".cell(),
+            "selected".cell(),
+            "author cert".cell(),
+            "author ca".cell(),
+            "distributor cert".cell(),
+        ]);
+
+    assert!(print_stdout(table).is_ok());
+
+    if !tizen_env.security_profile.is_empty() && tizen_env.security_profile != "default" {
+        find_profile(&tizen_env.studio_path, &tizen_env.security_profile)?;
+    }
+
+    Ok(0)
+}
+
+fn yes_or_empty(value: bool) -> String {
+    if value {
+        "yes".to_string()
+    } else {
+        "".to_string()
+    }
+}
@@ -1,18 +1,89 @@
+use crate::commands;
 use crate::error::TizenError;
 use crate::helpers::run_command;
-use crate::tizen_env::TizenEnv;
+use crate::sdb::{shell_quote, SdbClient};
+use crate::tizen_env::{ApplicationType, TizenEnv};
 use clap::ArgMatches;
+use colored::*;
 
 pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
-    let tizen_args = vec![
-        "run".to_string(),
-        "-p".to_string(),
-        tizen_env.app_id.clone(),
-    ];
+    let application = tizen_env.find_application(args.value_of("app"))?;
+
+    // Watch faces and widgets aren't launched, but their logs can still be followed
+    let exit_code = match application.app_type {
+        ApplicationType::Watch => {
+            println!(
+                "{} {}",
+                "Watch faces can't be launched, select it as watch face on the device:"
+                    .yellow()
+                    .bold(),
+                &application.app_id
+            );
+            0
+        }
+        ApplicationType::Widget => {
+            println!(
+                "{} {}",
+                "Widgets can't be launched, add it from the widget board on the device:"
+                    .yellow()
+                    .bold(),
+                &application.app_id
+            );
+            0
+        }
+        _ => match tizen_env.device_tool.as_str() {
+            "sdb" => run_sdb(tizen_env, &application.app_id)?,
+            "studio" => run_studio(tizen_env, args, &application.app_id)?,
+            _ => {
+                return Err(TizenError {
+                    message: format!("Unsupported device tool {}", &tizen_env.device_tool),
+                })
+            }
+        },
+    };
+
+    if args.is_present("log") {
+        return commands::log::stream(tizen_env, args, application);
+    }
+
+    Ok(exit_code)
+}
+
+fn run_sdb(tizen_env: &TizenEnv, app_id: &str) -> Result<i32, TizenError> {
+    let sdb = SdbClient::new(tizen_env);
+    let device = sdb.select_device()?;
+
+    let launch_command = format!("app_launcher -s {}", shell_quote(app_id));
+    println!("Running: {}", &launch_command.green().bold());
+
+    let mut is_launched = false;
+    let exit_code = sdb.shell(&device.serial, &launch_command, &mut |line| {
+        println!("{}", line);
+        if line.contains("successfully launched") {
+            is_launched = true;
+        }
+    })?;
+
+    if exit_code != 0 && !is_launched {
+        return Err(TizenError {
+            message: "cargo tizen run failed!".to_string(),
+        });
+    }
+
+    Ok(0)
+}
+
+fn run_studio(tizen_env: &TizenEnv, args: &ArgMatches, app_id: &str) -> Result<i32, TizenError> {
+    let mut tizen_args = vec!["run".to_string(), "-p".to_string(), app_id.to_string()];
+
+    if !tizen_env.target_serial.is_empty() {
+        tizen_args.push("-s".to_string());
+        tizen_args.push(tizen_env.target_serial.clone());
+    }
 
     let mut handle = run_command(
-        &tizen_env,
-        &args,
+        tizen_env,
+        args,
         &tizen_env.tizen_bin,
         &tizen_args,
         None,
@@ -0,0 +1,88 @@
+use crate::commands::build::run_cargo;
+use crate::error::TizenError;
+use crate::sdb::SdbClient;
+use crate::tizen_env::TizenEnv;
+use clap::ArgMatches;
+use colored::*;
+
+pub fn run(tizen_env: &TizenEnv, args: &ArgMatches) -> Result<i32, TizenError> {
+    // Like cargo test, the args after a second -- go to the test binaries, and
+    // the test name filter is given to them since the binaries run remotely
+    let test_args: Vec<String> = match args.values_of("test_args") {
+        Some(values) => values.map(|v| v.to_string()).collect(),
+        None => vec![],
+    };
+    let (mut cargo_extra_args, mut binary_args) = match test_args.iter().position(|arg| arg == "--")
+    {
+        Some(pos) => (test_args[..pos].to_vec(), test_args[pos + 1..].to_vec()),
+        None => (test_args, vec![]),
+    };
+
+    if cargo_extra_args
+        .first()
+        .map(|arg| !arg.starts_with('-'))
+        .unwrap_or(false)
+    {
+        binary_args.insert(0, cargo_extra_args.remove(0));
+    }
+
+    let mut cargo_args: Vec<String> = vec![
+        "test".to_string(),
+        "--no-run".to_string(),
+        format!("--target={}", &tizen_env.rust_triple),
+        "--message-format=json-render-diagnostics".to_string(),
+    ];
+
+    if tizen_env.is_release {
+        cargo_args.push("--release".to_string());
+    }
+
+    if let Some(cargo_package) = &tizen_env.cargo_package {
+        cargo_args.push(format!("--package={}", cargo_package));
+    }
+
+    let executables = run_cargo(tizen_env, args, &cargo_args, Some(cargo_extra_args), true)?;
+
+    if executables.is_empty() {
+        println!("{}", "No test binary built".yellow().bold());
+        return Ok(0);
+    }
+
+    let sdb = SdbClient::new(tizen_env);
+    let device = sdb.select_device()?;
+
+    for executable in executables.iter() {
+        let file_name = executable
+            .path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&executable.name);
+
+        println!(
+            "{} {} on {} ({})",
+            "Running".green().bold(),
+            file_name.yellow(),
+            &device.serial.yellow(),
+            &device.name
+        );
+
+        let exit_code = sdb.exec(
+            &device.serial,
+            &executable.path,
+            &binary_args,
+            &mut |line| println!("{}", line),
+        )?;
+
+        if exit_code != 0 {
+            eprintln!(
+                "{}",
+                format!("test failed, {} exited with code {}", file_name, exit_code)
+                    .bold()
+                    .red()
+            );
+            return Ok(exit_code);
+        }
+    }
+
+    Ok(0)
+}
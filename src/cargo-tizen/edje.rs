@@ -0,0 +1,310 @@
+use crate::error::TizenError;
+use crate::tizen_env::TizenEnv;
+use colored::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+pub const EDJE_DIR: &str = "res/edje";
+
+pub struct CompiledEdc {
+    pub dest: PathBuf,
+    pub edj_path: PathBuf,
+}
+
+// Like USER_EDCS of Studio projects, the .edc files at the top of tizen.edje.edc_dirs
+// are compiled with edje_cc, their sub folders only hold the included files. The .edj
+// files are kept in the target folder and compiled again when a source is newer
+pub fn compile_edcs(tizen_env: &TizenEnv) -> Result<Vec<CompiledEdc>, TizenError> {
+    let edc_files = find_edc_files(tizen_env)?;
+
+    if edc_files.is_empty() {
+        return Ok(vec![]);
+    }
+
+    if !Path::new(&tizen_env.edje_cc_bin).is_file() {
+        return Err(TizenError {
+            message: format!(
+                "edje_cc not found at {}, install the EFL tools of Tizen Studio or set tizen.edje_cc_path",
+                &tizen_env.edje_cc_bin
+            ),
+        });
+    }
+
+    check_edj_names(tizen_env, &edc_files)?;
+
+    let edje_output_dir = tizen_env.rust_output_dir().join("tizen-edje");
+    fs::create_dir_all(&edje_output_dir)?;
+
+    let sources_mtime = newest_mtime(tizen_env);
+    let mut compiled_edcs: Vec<CompiledEdc> = vec![];
+
+    for edc_file in edc_files.iter() {
+        let edj_name = edc_file.with_extension("edj");
+        let edj_name = edj_name.file_name().unwrap_or_default();
+        let edj_path = edje_output_dir.join(edj_name);
+
+        if is_outdated(&edj_path, sources_mtime) {
+            compile_edc(tizen_env, edc_file, &edj_path)?;
+        }
+
+        compiled_edcs.push(CompiledEdc {
+            dest: Path::new(EDJE_DIR).join(edj_name),
+            edj_path,
+        });
+    }
+
+    Ok(compiled_edcs)
+}
+
+// The .edj files all go to res/edje, two .edc with the same name would overwrite
+fn check_edj_names(tizen_env: &TizenEnv, edc_files: &[PathBuf]) -> Result<(), TizenError> {
+    let relative = |path: &Path| -> String {
+        path.strip_prefix(&tizen_env.base_path)
+            .unwrap_or(path)
+            .to_str()
+            .unwrap_or("")
+            .to_string()
+    };
+
+    for (index, edc_file) in edc_files.iter().enumerate() {
+        if let Some(other) = edc_files[..index]
+            .iter()
+            .find(|other| other.file_stem() == edc_file.file_stem())
+        {
+            return Err(TizenError {
+                message: format!(
+                    "{} and {} are both compiled to {}/{}.edj, rename one of them",
+                    relative(other),
+                    relative(edc_file),
+                    EDJE_DIR,
+                    edc_file
+                        .file_stem()
+                        .unwrap_or_default()
+                        .to_str()
+                        .unwrap_or("")
+                ),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn compile_edc(tizen_env: &TizenEnv, edc_file: &Path, edj_path: &Path) -> Result<(), TizenError> {
+    let mut edje_args: Vec<String> = vec![];
+
+    for (flag, dirs) in [
+        ("-id", &tizen_env.edc_image_dirs),
+        ("-sd", &tizen_env.edc_sound_dirs),
+        ("-fd", &tizen_env.edc_font_dirs),
+    ] {
+        for dir in existing_dirs(tizen_env, dirs) {
+            edje_args.push(flag.to_string());
+            edje_args.push(dir.to_str().unwrap_or("").to_string());
+        }
+    }
+
+    for dir in existing_dirs(tizen_env, &tizen_env.edc_dirs) {
+        edje_args.push(format!("-I{}", dir.to_str().unwrap_or("")));
+    }
+
+    edje_args.push(edc_file.to_str().unwrap_or("").to_string());
+    edje_args.push(edj_path.to_str().unwrap_or("").to_string());
+
+    println!(
+        "Running: {} {}",
+        &tizen_env.edje_cc_bin.green().bold(),
+        edje_args.join(" ").green().bold()
+    );
+
+    let status = Command::new(&tizen_env.edje_cc_bin)
+        .args(&edje_args)
+        .current_dir(&tizen_env.base_path)
+        .status()?;
+
+    if !status.success() {
+        // A partial .edj would be seen as up to date by the next package
+        let _ = fs::remove_file(edj_path);
+
+        return Err(TizenError {
+            message: format!(
+                "edje_cc failed to compile {}",
+                edc_file
+                    .strip_prefix(&tizen_env.base_path)
+                    .unwrap_or(edc_file)
+                    .to_str()
+                    .unwrap_or("")
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+fn find_edc_files(tizen_env: &TizenEnv) -> Result<Vec<PathBuf>, TizenError> {
+    let mut edc_files: Vec<PathBuf> = vec![];
+
+    for dir in existing_dirs(tizen_env, &tizen_env.edc_dirs) {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+
+            if path.is_file() && path.extension().map(|ext| ext == "edc").unwrap_or(false) {
+                edc_files.push(path);
+            }
+        }
+    }
+
+    edc_files.sort();
+
+    Ok(edc_files)
+}
+
+fn existing_dirs(tizen_env: &TizenEnv, dirs: &[String]) -> Vec<PathBuf> {
+    dirs.iter()
+        .map(|dir| tizen_env.base_path.join(dir))
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+fn is_outdated(edj_path: &Path, sources_mtime: Option<SystemTime>) -> bool {
+    match fs::metadata(edj_path).and_then(|m| m.modified()) {
+        Ok(edj_mtime) => sources_mtime.map(|m| m > edj_mtime).unwrap_or(true),
+        Err(_) => true,
+    }
+}
+
+// An .edc may include any file of the edje folders, so every .edj depends on all of them
+fn newest_mtime(tizen_env: &TizenEnv) -> Option<SystemTime> {
+    let mut newest: Option<SystemTime> = None;
+    let mut stack: Vec<PathBuf> = [
+        &tizen_env.edc_dirs,
+        &tizen_env.edc_image_dirs,
+        &tizen_env.edc_sound_dirs,
+        &tizen_env.edc_font_dirs,
+    ]
+    .iter()
+    .flat_map(|dirs| existing_dirs(tizen_env, dirs))
+    .collect();
+
+    while let Some(working_dir) = stack.pop() {
+        let entries = match fs::read_dir(&working_dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            if path.is_dir() {
+                stack.push(path);
+            } else if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                newest = newest.max(Some(modified));
+            }
+        }
+    }
+
+    newest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tizen_env::tests::tizen_env_in;
+    use std::fs::File;
+    use std::time::Duration;
+
+    const EDJE_CONFIG: &str = "[tizen.edje]\nedc_dirs = [\"edje\", \"edje2\", \"missing\"]\n";
+
+    fn write(project: &Path, file: &str, modified: SystemTime) {
+        let path = project.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, file).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn edc_files_are_found_at_the_top_of_the_edc_dirs() {
+        let project = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        for file in [
+            "edje/main.edc",
+            "edje/button.edc",
+            "edje/notes.txt",
+            "edje/includes/part.edc",
+            "edje2/list.edc",
+            "other/ignored.edc",
+        ] {
+            write(project.path(), file, now);
+        }
+
+        let tizen_env = tizen_env_in(project.path(), EDJE_CONFIG, &[]);
+        let edc_files = find_edc_files(&tizen_env).unwrap();
+
+        assert_eq!(
+            edc_files,
+            vec![
+                project.path().join("edje/button.edc"),
+                project.path().join("edje/main.edc"),
+                project.path().join("edje2/list.edc"),
+            ]
+        );
+        assert!(check_edj_names(&tizen_env, &edc_files).is_ok());
+    }
+
+    #[test]
+    fn edc_files_compiled_to_the_same_edj_are_rejected() {
+        let project = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        write(project.path(), "edje/main.edc", now);
+        write(project.path(), "edje2/main.edc", now);
+
+        let tizen_env = tizen_env_in(project.path(), EDJE_CONFIG, &[]);
+        let edc_files = find_edc_files(&tizen_env).unwrap();
+
+        assert_eq!(
+            check_edj_names(&tizen_env, &edc_files).err().map(|e| e.message),
+            Some(
+                "edje/main.edc and edje2/main.edc are both compiled to res/edje/main.edj, rename one of them"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn edj_files_are_outdated_by_any_edje_file() {
+        let project = tempfile::tempdir().unwrap();
+        let start = SystemTime::now() - Duration::from_secs(600);
+        write(project.path(), "edje/main.edc", start);
+        write(project.path(), "edje/includes/part.edc", start);
+        write(project.path(), "edje/images/icons/logo.png", start);
+        write(project.path(), "main.edj", start + Duration::from_secs(10));
+
+        let tizen_env = tizen_env_in(project.path(), EDJE_CONFIG, &[]);
+        let edj_path = project.path().join("main.edj");
+
+        assert_eq!(newest_mtime(&tizen_env), Some(start));
+        assert!(!is_outdated(&edj_path, newest_mtime(&tizen_env)));
+        assert!(is_outdated(&project.path().join("other.edj"), Some(start)));
+        assert!(is_outdated(&edj_path, None));
+
+        // An image of a nested folder changed after the last compilation
+        write(
+            project.path(),
+            "edje/images/icons/logo.png",
+            start + Duration::from_secs(20),
+        );
+
+        assert_eq!(
+            newest_mtime(&tizen_env),
+            Some(start + Duration::from_secs(20))
+        );
+        assert!(is_outdated(&edj_path, newest_mtime(&tizen_env)));
+    }
+}
@@ -41,3 +41,11 @@ impl From<String> for TizenError {
         TizenError { message: str_value }
     }
 }
+
+impl From<zip::result::ZipError> for TizenError {
+    fn from(error: zip::result::ZipError) -> Self {
+        TizenError {
+            message: format!("{}", error),
+        }
+    }
+}
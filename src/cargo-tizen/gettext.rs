@@ -0,0 +1,759 @@
+use crate::error::TizenError;
+use crate::tizen_env::TizenEnv;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const LOCALE_DIR: &str = "res/locale";
+
+const MO_MAGIC: u32 = 0x9504_12de;
+const CONTEXT_SEPARATOR: char = '\u{4}';
+
+// The macros of gettext-rs with the position of their context, msgid and plural args
+const GETTEXT_MACROS: [(&str, Option<usize>, usize, Option<usize>); 4] = [
+    ("gettext", None, 0, None),
+    ("ngettext", None, 0, Some(1)),
+    ("pgettext", Some(0), 1, None),
+    ("npgettext", Some(0), 1, Some(2)),
+];
+
+pub struct CompiledCatalog {
+    pub dest: PathBuf,
+    pub content: Vec<u8>,
+}
+
+#[derive(Default)]
+struct Message {
+    context: Option<String>,
+    id: Option<String>,
+    id_plural: Option<String>,
+    translations: Vec<String>,
+    is_fuzzy: bool,
+}
+
+// The context and msgid of an extracted message
+type MessageKey = (Option<String>, String);
+
+enum Field {
+    Context,
+    Id,
+    IdPlural,
+    Translation(usize),
+}
+
+// Each po/<lang>.po becomes res/locale/<lang>/LC_MESSAGES/<domain>.mo
+pub fn compile_catalogs(tizen_env: &TizenEnv) -> Result<Vec<CompiledCatalog>, TizenError> {
+    let po_dir = tizen_env.base_path.join(&tizen_env.po_dir);
+
+    if !po_dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut po_files: Vec<PathBuf> = vec![];
+
+    for entry in fs::read_dir(&po_dir)? {
+        let path = entry?.path();
+
+        if path.is_file() && path.extension().map(|ext| ext == "po").unwrap_or(false) {
+            po_files.push(path);
+        }
+    }
+
+    po_files.sort();
+
+    let mut catalogs: Vec<CompiledCatalog> = vec![];
+
+    for po_file in po_files.iter() {
+        let lang = po_file
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let relative_po = po_file
+            .strip_prefix(&tizen_env.base_path)
+            .unwrap_or(po_file);
+
+        let messages =
+            parse_po(&fs::read_to_string(po_file)?).map_err(|(line, message)| TizenError {
+                message: format!("{}:{}: {}", relative_po.display(), line, message),
+            })?;
+
+        catalogs.push(CompiledCatalog {
+            dest: Path::new(LOCALE_DIR)
+                .join(lang)
+                .join("LC_MESSAGES")
+                .join(format!("{}.mo", &tizen_env.text_domain)),
+            content: write_mo(&messages),
+        });
+    }
+
+    Ok(catalogs)
+}
+
+// Collects the gettext!, ngettext!, pgettext! and npgettext! calls of src in a .pot
+pub fn extract_messages(tizen_env: &TizenEnv) -> Result<(String, usize), TizenError> {
+    let mut rs_files: Vec<PathBuf> = vec![];
+    let mut stack: Vec<PathBuf> = vec![tizen_env.base_path.join("src")];
+
+    while let Some(working_dir) = stack.pop() {
+        if !working_dir.is_dir() {
+            continue;
+        }
+
+        for entry in fs::read_dir(&working_dir)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                stack.push(path);
+            } else if path.extension().map(|ext| ext == "rs").unwrap_or(false) {
+                rs_files.push(path);
+            }
+        }
+    }
+
+    rs_files.sort();
+
+    // Keyed by context and msgid, in the order they are first found
+    let mut keys: Vec<MessageKey> = vec![];
+    let mut messages: BTreeMap<MessageKey, (Option<String>, Vec<String>)> = BTreeMap::new();
+
+    for rs_file in rs_files.iter() {
+        let relative_path = rs_file
+            .strip_prefix(&tizen_env.base_path)
+            .unwrap_or(rs_file);
+
+        for (line, context, id, id_plural) in find_gettext_calls(&fs::read_to_string(rs_file)?) {
+            let key = (context, id);
+            let reference = format!("{}:{}", relative_path.display(), line);
+
+            match messages.get_mut(&key) {
+                Some((plural, references)) => {
+                    if plural.is_none() {
+                        *plural = id_plural;
+                    }
+                    references.push(reference);
+                }
+                None => {
+                    keys.push(key.clone());
+                    messages.insert(key, (id_plural, vec![reference]));
+                }
+            }
+        }
+    }
+
+    let mut pot = String::new();
+
+    pot.push_str("msgid \"\"\nmsgstr \"\"\n");
+    pot.push_str(&format!(
+        "\"Project-Id-Version: {} {}\\n\"\n",
+        &tizen_env.cargo_pkg_name, &tizen_env.cargo_version
+    ));
+    pot.push_str("\"MIME-Version: 1.0\\n\"\n");
+    pot.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+    pot.push_str("\"Content-Transfer-Encoding: 8bit\\n\"\n");
+    pot.push_str("\"Plural-Forms: nplurals=INTEGER; plural=EXPRESSION;\\n\"\n");
+
+    for key in keys.iter() {
+        let (id_plural, references) = &messages[key];
+        let (context, id) = key;
+
+        pot.push_str(&format!("\n#: {}\n", references.join(" ")));
+
+        if let Some(context) = context {
+            pot.push_str(&format!("msgctxt {}\n", po_quote(context)));
+        }
+
+        pot.push_str(&format!("msgid {}\n", po_quote(id)));
+
+        match id_plural {
+            Some(id_plural) => {
+                pot.push_str(&format!("msgid_plural {}\n", po_quote(id_plural)));
+                pot.push_str("msgstr[0] \"\"\nmsgstr[1] \"\"\n");
+            }
+            None => pot.push_str("msgstr \"\"\n"),
+        }
+    }
+
+    Ok((pot, keys.len()))
+}
+
+fn parse_po(content: &str) -> Result<Vec<Message>, (usize, String)> {
+    let mut messages: Vec<Message> = vec![];
+    let mut message = Message::default();
+    let mut field: Option<Field> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+
+        // A comment or a msgctxt/msgid after the translations starts the next message
+        let starts_message = line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with("msgctxt ")
+            || line.starts_with("msgid ");
+
+        if starts_message && !message.translations.is_empty() {
+            messages.push(std::mem::take(&mut message));
+            field = None;
+        }
+
+        if line.is_empty() || line.starts_with("#~") {
+            continue;
+        }
+
+        if let Some(flags) = line.strip_prefix("#,") {
+            if flags.split(',').any(|flag| flag.trim() == "fuzzy") {
+                message.is_fuzzy = true;
+            }
+            continue;
+        }
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, value) = match line.find('"') {
+            Some(pos) => (line[..pos].trim(), &line[pos..]),
+            None => return Err((line_number, format!("expected a string in '{}'", line))),
+        };
+
+        let value = po_unquote(value).ok_or((line_number, "invalid string".to_string()))?;
+
+        field = match keyword {
+            "" => field,
+            "msgctxt" => Some(Field::Context),
+            "msgid" => Some(Field::Id),
+            "msgid_plural" => Some(Field::IdPlural),
+            "msgstr" => Some(Field::Translation(0)),
+            _ => match keyword
+                .strip_prefix("msgstr[")
+                .and_then(|index| index.strip_suffix(']'))
+                .and_then(|index| index.parse::<usize>().ok())
+            {
+                Some(index) => Some(Field::Translation(index)),
+                None => return Err((line_number, format!("unknown keyword {}", keyword))),
+            },
+        };
+
+        let target = match &field {
+            Some(Field::Context) => message.context.get_or_insert_with(String::new),
+            Some(Field::Id) => message.id.get_or_insert_with(String::new),
+            Some(Field::IdPlural) => message.id_plural.get_or_insert_with(String::new),
+            Some(Field::Translation(index)) => {
+                if message.translations.len() <= *index {
+                    message.translations.resize(index + 1, String::new());
+                }
+                &mut message.translations[*index]
+            }
+            None => return Err((line_number, "string outside of a message".to_string())),
+        };
+
+        target.push_str(&value);
+    }
+
+    if !message.translations.is_empty() {
+        messages.push(message);
+    }
+
+    Ok(messages)
+}
+
+// Like msgfmt, untranslated and fuzzy messages are left out, except the header
+fn write_mo(messages: &[Message]) -> Vec<u8> {
+    let mut entries: BTreeMap<String, String> = BTreeMap::new();
+
+    for message in messages.iter() {
+        let id = match &message.id {
+            Some(id) => id,
+            None => continue,
+        };
+
+        let is_header = id.is_empty() && message.context.is_none();
+
+        if !is_header && (message.is_fuzzy || message.translations.iter().all(|t| t.is_empty())) {
+            continue;
+        }
+
+        let mut key = match &message.context {
+            Some(context) => format!("{}{}{}", context, CONTEXT_SEPARATOR, id),
+            None => id.clone(),
+        };
+
+        if let Some(id_plural) = &message.id_plural {
+            key.push('\0');
+            key.push_str(id_plural);
+        }
+
+        entries.insert(key, message.translations.join("\0"));
+    }
+
+    let count = entries.len() as u32;
+    let ids_offset = 28;
+    let translations_offset = ids_offset + count * 8;
+    let mut strings_offset = translations_offset + count * 8;
+
+    let mut ids_table: Vec<u8> = vec![];
+    let mut translations_table: Vec<u8> = vec![];
+    let mut strings: Vec<u8> = vec![];
+
+    for (table, values) in [
+        (&mut ids_table, entries.keys().collect::<Vec<&String>>()),
+        (&mut translations_table, entries.values().collect()),
+    ] {
+        for value in values {
+            table.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            table.extend_from_slice(&strings_offset.to_le_bytes());
+
+            strings.extend_from_slice(value.as_bytes());
+            strings.push(0);
+            strings_offset += value.len() as u32 + 1;
+        }
+    }
+
+    let mut mo: Vec<u8> = vec![];
+
+    // magic, revision, count, tables offsets, and an empty hash table
+    for value in [
+        MO_MAGIC,
+        0,
+        count,
+        ids_offset,
+        translations_offset,
+        0,
+        translations_offset + count * 8,
+    ] {
+        mo.extend_from_slice(&value.to_le_bytes());
+    }
+
+    mo.extend(ids_table);
+    mo.extend(translations_table);
+    mo.extend(strings);
+
+    mo
+}
+
+fn po_unquote(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut result = String::new();
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        result.push(match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'a' => '\u{7}',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'v' => '\u{b}',
+            other => other,
+        });
+    }
+
+    Some(result)
+}
+
+fn po_quote(value: &str) -> String {
+    let escape = |line: &str| {
+        line.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+            .replace('\t', "\\t")
+            .replace('\r', "\\r")
+    };
+
+    let lines: Vec<&str> = value.split_inclusive('\n').collect();
+
+    if lines.len() <= 1 {
+        return format!("\"{}\"", escape(value));
+    }
+
+    // Multi-line strings are written one line per string, as xgettext does
+    let mut quoted = "\"\"".to_string();
+
+    for line in lines {
+        quoted.push_str(&format!("\n\"{}\"", escape(line)));
+    }
+
+    quoted
+}
+
+// Returns the line, context, msgid and plural of each call with literal args,
+// comments, strings and char literals are skipped while looking for the macros
+fn find_gettext_calls(source: &str) -> Vec<(usize, Option<String>, String, Option<String>)> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut calls = vec![];
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 2;
+        } else if c == '"' || (c == 'r' && matches!(chars.get(i + 1), Some('"') | Some('#'))) {
+            let start = i;
+            i = parse_rust_string(&chars, i)
+                .map(|(_, end)| end)
+                .unwrap_or(i + 1);
+            line += chars[start..i.min(chars.len())]
+                .iter()
+                .filter(|c| **c == '\n')
+                .count();
+        } else if c == '\'' {
+            i = skip_char_literal(&chars, i);
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+
+            let ident: String = chars[start..i].iter().collect();
+
+            let gettext_macro = GETTEXT_MACROS.iter().find(|(name, ..)| *name == ident);
+
+            if let (Some(gettext_macro), Some('!')) = (gettext_macro, chars.get(i)) {
+                if let Some(args) = parse_literal_args(&chars, i + 1) {
+                    let (_, context_pos, id_pos, plural_pos) = gettext_macro;
+
+                    if let Some(id) = args.get(*id_pos).cloned().flatten() {
+                        calls.push((
+                            line,
+                            context_pos.and_then(|pos| args.get(pos).cloned().flatten()),
+                            id,
+                            plural_pos.and_then(|pos| args.get(pos).cloned().flatten()),
+                        ));
+                    }
+                }
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    calls
+}
+
+// The args of a macro call, None for the args that aren't string literals
+fn parse_literal_args(chars: &[char], start: usize) -> Option<Vec<Option<String>>> {
+    let mut i = skip_whitespace(chars, start);
+
+    if !matches!(chars.get(i), Some('(') | Some('[') | Some('{')) {
+        return None;
+    }
+
+    i = skip_whitespace(chars, i + 1);
+
+    let mut args = vec![];
+
+    loop {
+        let is_literal = chars.get(i) == Some(&'"')
+            || (chars.get(i) == Some(&'r') && matches!(chars.get(i + 1), Some('"') | Some('#')));
+
+        match is_literal {
+            true => {
+                let (value, end) = parse_rust_string(chars, i)?;
+                args.push(Some(value));
+                i = skip_whitespace(chars, end);
+            }
+            false => {
+                // Only the leading literal args are needed, stop at the first other one
+                args.push(None);
+                return Some(args);
+            }
+        }
+
+        match chars.get(i) {
+            Some(',') => i = skip_whitespace(chars, i + 1),
+            _ => return Some(args),
+        }
+    }
+}
+
+// Parses "..." or r#"..."# at start, returns the value and the index after it
+fn parse_rust_string(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let mut i = start;
+
+    if chars.get(i) == Some(&'r') {
+        i += 1;
+        let mut hashes = 0;
+        while chars.get(i) == Some(&'#') {
+            hashes += 1;
+            i += 1;
+        }
+
+        if chars.get(i) != Some(&'"') {
+            return None;
+        }
+        i += 1;
+
+        let value_start = i;
+        while i < chars.len() {
+            if chars[i] == '"' && (1..=hashes).all(|h| chars.get(i + h) == Some(&'#')) {
+                return Some((chars[value_start..i].iter().collect(), i + 1 + hashes));
+            }
+            i += 1;
+        }
+
+        return None;
+    }
+
+    if chars.get(i) != Some(&'"') {
+        return None;
+    }
+    i += 1;
+
+    let mut value = String::new();
+
+    while i < chars.len() {
+        match chars[i] {
+            '"' => return Some((value, i + 1)),
+            '\\' => {
+                i += 1;
+                match chars.get(i)? {
+                    'n' => value.push('\n'),
+                    't' => value.push('\t'),
+                    'r' => value.push('\r'),
+                    '0' => value.push('\0'),
+                    'x' => {
+                        let hex: String = chars.get(i + 1..i + 3)?.iter().collect();
+                        value.push(char::from(u8::from_str_radix(&hex, 16).ok()?));
+                        i += 2;
+                    }
+                    'u' => {
+                        let end = i + chars[i..].iter().position(|c| *c == '}')?;
+                        let hex: String = chars[i + 2..end].iter().collect();
+                        value.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                        i = end;
+                    }
+                    // A line continuation skips the newline and the leading whitespace
+                    '\n' => {
+                        while chars.get(i + 1).map(|c| c.is_whitespace()).unwrap_or(false) {
+                            i += 1;
+                        }
+                    }
+                    other => value.push(*other),
+                }
+                i += 1;
+            }
+            c => {
+                value.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    None
+}
+
+// Skips a char literal, a lifetime only skips the quote
+fn skip_char_literal(chars: &[char], start: usize) -> usize {
+    match (chars.get(start + 1), chars.get(start + 2)) {
+        (Some('\\'), _) => {
+            let mut i = start + 3;
+            while i < chars.len() && chars[i] != '\'' {
+                i += 1;
+            }
+            i + 1
+        }
+        (Some(_), Some('\'')) => start + 3,
+        _ => start + 1,
+    }
+}
+
+fn skip_whitespace(chars: &[char], start: usize) -> usize {
+    let mut i = start;
+    while chars.get(i).map(|c| c.is_whitespace()).unwrap_or(false) {
+        i += 1;
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PO: &str = r#"# French translations
+msgid ""
+msgstr ""
+"Project-Id-Version: app 1.0\n"
+"Content-Type: text/plain; charset=UTF-8\n"
+"Plural-Forms: nplurals=2; plural=(n > 1);\n"
+
+#: src/main.rs:10
+msgctxt "menu"
+msgid "Open"
+msgstr "Ouvrir"
+
+msgid "Open"
+msgstr "Ouvrir le fichier"
+
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] "%d fichier"
+msgstr[1] "%d fichiers"
+
+msgid "Say \"hi\"\n"
+msgstr ""
+"Dis \"salut\"\n"
+
+#, c-format, fuzzy
+msgid "Close"
+msgstr "Fermer"
+
+msgid "Untranslated"
+msgstr ""
+
+#~ msgid "Removed"
+#~ msgstr "Supprimé"
+"#;
+
+    fn read_u32(mo: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes([mo[offset], mo[offset + 1], mo[offset + 2], mo[offset + 3]])
+    }
+
+    // Reads the strings of a table, checking each one ends with a NUL
+    fn read_table(mo: &[u8], table_offset: usize, count: usize) -> Vec<String> {
+        (0..count)
+            .map(|index| {
+                let length = read_u32(mo, table_offset + index * 8) as usize;
+                let offset = read_u32(mo, table_offset + index * 8 + 4) as usize;
+                assert_eq!(mo[offset + length], 0);
+
+                String::from_utf8(mo[offset..offset + length].to_vec()).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn po_files_are_parsed() {
+        let messages = parse_po(PO).unwrap();
+
+        assert_eq!(messages.len(), 7);
+        assert_eq!(messages[0].id.as_deref(), Some(""));
+        assert!(messages[0].translations[0].starts_with("Project-Id-Version: app 1.0\n"));
+        assert_eq!(messages[1].context.as_deref(), Some("menu"));
+        assert_eq!(messages[3].id_plural.as_deref(), Some("%d files"));
+        assert_eq!(messages[3].translations, vec!["%d fichier", "%d fichiers"]);
+        assert_eq!(messages[4].id.as_deref(), Some("Say \"hi\"\n"));
+        assert_eq!(messages[4].translations, vec!["Dis \"salut\"\n"]);
+        assert!(messages[5].is_fuzzy);
+        assert!(!messages[6].is_fuzzy);
+        assert!(messages
+            .iter()
+            .all(|message| message.id.as_deref() != Some("Removed")));
+    }
+
+    #[test]
+    fn po_errors_have_the_line_number() {
+        let result = parse_po("msgid \"Open\"\nmsgstr \"Ouvrir\"\nmsgtext \"Other\"\n");
+
+        assert_eq!(
+            result.err(),
+            Some((3, "unknown keyword msgtext".to_string()))
+        );
+    }
+
+    #[test]
+    fn mo_files_have_sorted_tables() {
+        let mo = write_mo(&parse_po(PO).unwrap());
+
+        assert_eq!(read_u32(&mo, 0), MO_MAGIC);
+        assert_eq!(read_u32(&mo, 4), 0);
+
+        let count = read_u32(&mo, 8) as usize;
+        let ids_offset = read_u32(&mo, 12) as usize;
+        let translations_offset = read_u32(&mo, 16) as usize;
+        assert_eq!(count, 5);
+        assert_eq!(ids_offset, 28);
+        assert_eq!(translations_offset, 28 + count * 8);
+        assert_eq!(read_u32(&mo, 20), 0);
+
+        let ids = read_table(&mo, ids_offset, count);
+        let translations = read_table(&mo, translations_offset, count);
+
+        assert_eq!(
+            ids,
+            vec![
+                "",
+                "%d file\0%d files",
+                "Open",
+                "Say \"hi\"\n",
+                "menu\u{4}Open",
+            ]
+        );
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(translations[0].contains("Plural-Forms: nplurals=2; plural=(n > 1);\n"));
+        assert_eq!(
+            &translations[1..],
+            &[
+                "%d fichier\0%d fichiers",
+                "Ouvrir le fichier",
+                "Dis \"salut\"\n",
+                "Ouvrir",
+            ]
+        );
+    }
+
+    #[test]
+    fn gettext_calls_are_found_outside_comments_and_strings() {
+        let source = r##"fn main() {
+    // gettext!("commented out");
+    /* ngettext!("block", "blocks", n)
+       */
+    let quote = '"';
+    let text: &'static str = "gettext!(\"inside a string\")";
+    let label = gettext!("Hello \"world\"\n\u{e9}\x21");
+    let raw = gettext!(r#"Raw "quoted" \n"#);
+    let files = ngettext!("One file", "{} files", count);
+    let menu = pgettext!("menu", "Open");
+    let dynamic = gettext!(name);
+    let multi = gettext!(
+        "Multi \
+         line"
+    );
+    let last = npgettext!["menu", "Item", "Items", n];
+}
+"##;
+
+        assert_eq!(
+            find_gettext_calls(source),
+            vec![
+                (7, None, "Hello \"world\"\né!".to_string(), None),
+                (8, None, "Raw \"quoted\" \\n".to_string(), None),
+                (
+                    9,
+                    None,
+                    "One file".to_string(),
+                    Some("{} files".to_string())
+                ),
+                (10, Some("menu".to_string()), "Open".to_string(), None),
+                (12, None, "Multi line".to_string(), None),
+                (
+                    16,
+                    Some("menu".to_string()),
+                    "Item".to_string(),
+                    Some("Items".to_string())
+                ),
+            ]
+        );
+    }
+}
@@ -1,7 +1,6 @@
 use crate::tizen_env::TizenEnv;
 use clap::ArgMatches;
 use colored::*;
-use dialoguer::Confirm;
 use std::collections::HashMap;
 use std::path::Path;
 use std::process::Child;
@@ -16,45 +15,66 @@ pub fn run_command(
     include_build_env: bool,
     current_dir: Option<&Path>,
 ) -> Child {
+    let command = make_command(
+        tizen_env,
+        args_m,
+        name,
+        base_args,
+        last_args,
+        include_build_env,
+        current_dir,
+    )
+    .spawn();
+
+    match command {
+        Ok(c) => c,
+        Err(_) => {
+            println!("Failed to launch {}", name);
+            std::process::exit(1);
+        }
+    }
+}
+
+pub fn make_command(
+    tizen_env: &TizenEnv,
+    args_m: &ArgMatches,
+    name: &str,
+    base_args: &[String],
+    last_args: Option<Vec<String>>,
+    include_build_env: bool,
+    current_dir: Option<&Path>,
+) -> Command {
     let forward_args: Vec<String> = match args_m.values_of("forward_args") {
         Some(args) => args.map(|v| v.to_string()).collect(),
         None => vec![],
     };
 
-    let last_args = match last_args {
-        Some(v) => v,
-        None => vec![],
-    };
+    let last_args = last_args.unwrap_or_default();
 
     let working_dir = current_dir.unwrap_or(&tizen_env.base_path);
 
     println!(
         "Running: {} {}",
         &name.green().bold(),
-        [&base_args, &forward_args[..], &last_args[..]]
+        [base_args, &forward_args[..], &last_args[..]]
             .concat()
             .join(" ")
             .green()
             .bold()
     );
 
-    let command = Command::new(name)
-        .args([&base_args, &forward_args[..], &last_args[..]].concat())
+    let mut command = Command::new(name);
+
+    command
+        .args([base_args, &forward_args[..], &last_args[..]].concat())
         .envs(if include_build_env {
             make_process_env(tizen_env)
         } else {
             HashMap::new()
         })
-        .current_dir(working_dir)
-        .spawn();
+        .current_dir(working_dir);
 
-    match command {
-        Ok(c) => c,
-        Err(_) => {
-            println!("Failed to launch {}", name);
-            std::process::exit(1);
-        }
-    }
+    command
 }
 
 pub fn make_process_env(tizen_env: &TizenEnv) -> HashMap<String, String> {
@@ -71,9 +91,28 @@ pub fn make_process_env(tizen_env: &TizenEnv) -> HashMap<String, String> {
     env_map
 }
 
-pub fn ask(question: &str) -> bool {
-    Confirm::new()
-        .with_prompt(question)
-        .interact()
-        .unwrap_or(false)
+pub fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+
+    let (mut p, mut v) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == value[v]) {
+            p += 1;
+            v += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, v));
+            p += 1;
+        } else if let Some((star_p, star_v)) = backtrack {
+            p = star_p + 1;
+            v = star_v + 1;
+            backtrack = Some((star_p, star_v + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
 }
@@ -0,0 +1,70 @@
+use crate::error::TizenError;
+use crate::helpers::make_process_env;
+use crate::tizen_env::TizenEnv;
+use colored::*;
+use std::process::Command;
+
+pub enum Hook {
+    PrePackage,
+    PostPackage,
+}
+
+impl Hook {
+    pub fn name(&self) -> &str {
+        match self {
+            Hook::PrePackage => "pre_package",
+            Hook::PostPackage => "post_package",
+        }
+    }
+
+    fn command<'a>(&self, tizen_env: &'a TizenEnv) -> &'a str {
+        match self {
+            Hook::PrePackage => &tizen_env.pre_package_hook,
+            Hook::PostPackage => &tizen_env.post_package_hook,
+        }
+    }
+}
+
+// Runs a [tizen.hooks] command with sh from the project folder, the staging and
+// tpk folders are given in TIZEN_STAGING_DIR and TIZEN_TPK_DIR
+pub fn run_hook(tizen_env: &TizenEnv, hook_type: Hook) -> Result<(), TizenError> {
+    let name = hook_type.name();
+    let hook = hook_type.command(tizen_env);
+
+    if hook.trim().is_empty() {
+        return Ok(());
+    }
+
+    println!(
+        "Running {} hook: {}",
+        name.green().bold(),
+        hook.green().bold()
+    );
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(hook)
+        .current_dir(&tizen_env.base_path)
+        .envs(make_process_env(tizen_env))
+        .env("TIZEN_STAGING_DIR", tizen_env.tizen_output_dir())
+        .env("TIZEN_TPK_DIR", tizen_env.tizen_output_tpk_dir())
+        .status()
+        .map_err(|e| TizenError {
+            message: format!("Failed to run the {} hook: {}", name, e),
+        })?;
+
+    if !status.success() {
+        return Err(TizenError {
+            message: format!(
+                "The {} hook failed with exit code {}",
+                name,
+                status
+                    .code()
+                    .map(|code| code.to_string())
+                    .unwrap_or_else(|| "none".to_string())
+            ),
+        });
+    }
+
+    Ok(())
+}
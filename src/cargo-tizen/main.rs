@@ -3,9 +3,20 @@ use clap::Arg;
 use clap::{App, AppSettings, SubCommand};
 use std::{env, process};
 mod commands;
+mod edje;
 mod error;
+mod gettext;
 mod helpers;
+mod hooks;
+mod manifest;
+mod profiles;
+mod sdb;
+mod signer;
+mod staging;
+mod sync_files;
 mod tizen_env;
+mod tpk;
+mod workspace;
 use colored::*;
 use tizen_env::TizenEnv;
 
@@ -14,6 +25,11 @@ fn main() {
     let forward_args = make_forward_arg();
     let assume_yes_arg = make_assume_yes_arg();
     let release_arg = make_release_arg();
+    let bin_arg = make_bin_arg();
+    let app_arg = make_app_arg();
+    let serial_arg = make_serial_arg();
+    let log_arg = make_log_arg();
+    let new_args = make_new_args();
 
     let app_matches = App::new(env!("CARGO_PKG_NAME"))
         .version(env!("CARGO_PKG_VERSION"))
@@ -26,6 +42,7 @@ fn main() {
                 .about("Wrapper arround cargo build")
                 .args(&tizen_env_args)
                 .arg(&release_arg)
+                .arg(&bin_arg)
                 .arg(&forward_args),
         )
         .subcommand(
@@ -34,12 +51,14 @@ fn main() {
                 .args(&tizen_env_args)
                 .arg(&assume_yes_arg)
                 .arg(&release_arg)
+                .arg(&bin_arg)
                 .arg(&forward_args),
         )
         .subcommand(
             SubCommand::with_name("install")
                 .about("Wrapper arround tizen install")
                 .args(&tizen_env_args)
+                .arg(&serial_arg)
                 .arg(&release_arg)
                 .arg(&forward_args),
         )
@@ -47,8 +66,39 @@ fn main() {
             SubCommand::with_name("run")
                 .about("Wrapper arround tizen run")
                 .args(&tizen_env_args)
+                .arg(&serial_arg)
+                .arg(&app_arg)
+                .arg(&log_arg)
                 .arg(&forward_args),
         )
+        .subcommand(
+            SubCommand::with_name("test")
+                .about("Build the tests and run them on the target")
+                .args(&tizen_env_args)
+                .arg(&serial_arg)
+                .arg(&release_arg)
+                .arg(
+                    Arg::with_name("test_args")
+                        .multiple(true)
+                        .help("Cargo args, and the test binary args after --"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("exec")
+                .about("Push a binary to the target and run it (cargo runner)")
+                .args(&tizen_env_args)
+                .arg(&serial_arg)
+                .arg(
+                    Arg::with_name("binary")
+                        .required(true)
+                        .help("Binary built for the target"),
+                )
+                .arg(
+                    Arg::with_name("exec_args")
+                        .multiple(true)
+                        .help("Args of the binary (after -- when they start with -)"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("clean")
                 .about("Wrapper arround cargo clean")
@@ -61,6 +111,18 @@ fn main() {
                 .about("Show config used for building the app")
                 .args(&tizen_env_args)
                 .arg(&release_arg)
+                .arg(
+                    Arg::with_name("runner")
+                        .long("runner")
+                        .required(false)
+                        .help("Print the .cargo/config.toml runner for the tizen targets"),
+                )
+                .arg(
+                    Arg::with_name("files")
+                        .long("files")
+                        .required(false)
+                        .help("Print the files packaged from sync_files"),
+                )
                 .arg(
                     Arg::with_name("env_key")
                         .required(false)
@@ -68,20 +130,233 @@ fn main() {
                         .help("Detail about config key"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("new")
+                .about("Create a new tizen project")
+                .arg(
+                    Arg::with_name("path")
+                        .required(true)
+                        .takes_value(true)
+                        .help("Folder of the new project"),
+                )
+                .args(&new_args),
+        )
+        .subcommand(
+            SubCommand::with_name("init")
+                .about("Create a tizen project in an existing folder")
+                .arg(
+                    Arg::with_name("path")
+                        .required(false)
+                        .takes_value(true)
+                        .help("Folder of the project (defaults to the current folder)"),
+                )
+                .args(&new_args),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about("Validate tizen-manifest.xml")
+                .args(&tizen_env_args),
+        )
+        .subcommand(
+            SubCommand::with_name("log")
+                .about("Stream the app logs from dlogutil")
+                .args(&tizen_env_args)
+                .arg(&serial_arg)
+                .arg(&app_arg)
+                .arg(
+                    Arg::with_name("tag")
+                        .long("tag")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Log tag to show (defaults to the app id and exec)"),
+                )
+                .arg(
+                    Arg::with_name("all")
+                        .long("all")
+                        .required(false)
+                        .help("Show the logs of every process"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("manifest")
+                .about("Edit tizen-manifest.xml")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .args(&tizen_env_args)
+                .subcommand(
+                    SubCommand::with_name("privilege")
+                        .about("Add or remove privileges")
+                        .setting(AppSettings::SubcommandRequiredElseHelp)
+                        .subcommand(
+                            SubCommand::with_name("add")
+                                .about("Add privileges")
+                                .arg(make_privilege_arg()),
+                        )
+                        .subcommand(
+                            SubCommand::with_name("remove")
+                                .about("Remove privileges")
+                                .arg(make_privilege_arg()),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("feature")
+                        .about("Add or remove features")
+                        .setting(AppSettings::SubcommandRequiredElseHelp)
+                        .subcommand(
+                            SubCommand::with_name("add")
+                                .about("Add a feature (or change its value)")
+                                .arg(
+                                    Arg::with_name("name").required(true).help(
+                                        "Feature (e.g. http://tizen.org/feature/network.wifi)",
+                                    ),
+                                )
+                                .arg(
+                                    Arg::with_name("value")
+                                        .required(false)
+                                        .default_value("true")
+                                        .help("Feature value"),
+                                ),
+                        )
+                        .subcommand(
+                            SubCommand::with_name("remove")
+                                .about("Remove a feature")
+                                .arg(Arg::with_name("name").required(true).help("Feature")),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("set")
+                        .about("Set a manifest value")
+                        .arg(
+                            Arg::with_name("key")
+                                .required(true)
+                                .possible_values(&[
+                                    "version",
+                                    "api-version",
+                                    "package",
+                                    "profile",
+                                    "appid",
+                                    "exec",
+                                    "label",
+                                    "type",
+                                ])
+                                .help("Manifest key"),
+                        )
+                        .arg(Arg::with_name("value").required(true).help("New value"))
+                        .arg(
+                            Arg::with_name("app")
+                                .long("app")
+                                .takes_value(true)
+                                .required(false)
+                                .help(
+                                    "Application id to change (defaults to the first application)",
+                                ),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("devices")
+                .about("List the devices and emulators connected to sdb")
+                .args(&tizen_env_args)
+                .arg(&serial_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("emulator")
+                .about("Manage the emulators with em-cli")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .args(&tizen_env_args)
+                .subcommand(SubCommand::with_name("list").about("List the emulators"))
+                .subcommand(
+                    SubCommand::with_name("start")
+                        .about("Start an emulator and wait for it")
+                        .arg(make_emulator_name_arg(false)),
+                )
+                .subcommand(
+                    SubCommand::with_name("stop")
+                        .about("Stop an emulator")
+                        .arg(make_emulator_name_arg(false)),
+                )
+                .subcommand(
+                    SubCommand::with_name("create")
+                        .about("Create an emulator")
+                        .arg(make_emulator_name_arg(true))
+                        .arg(
+                            Arg::with_name("platform")
+                                .long("platform")
+                                .takes_value(true)
+                                .required(true)
+                                .help("Platform (e.g. wearable-5.5-circle-x86)"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("i18n")
+                .about("Manage the gettext translations")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .args(&tizen_env_args)
+                .subcommand(
+                    SubCommand::with_name("extract")
+                        .about("Extract the gettext! messages of src into a .pot")
+                        .arg(
+                            Arg::with_name("output")
+                                .long("output")
+                                .short("o")
+                                .takes_value(true)
+                                .help("Path of the .pot (default po/<domain>.pot)"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("profiles")
+                .about("List the security profiles from Tizen Studio")
+                .args(&tizen_env_args),
+        )
         .subcommand(
             SubCommand::with_name("dev")
                 .about("Build, package, install and run")
                 .args(&tizen_env_args)
+                .arg(&serial_arg)
+                .arg(&app_arg)
+                .arg(&log_arg)
                 .arg(&assume_yes_arg)
                 .arg(&release_arg)
+                .arg(&bin_arg)
+                .arg(&forward_args),
+        )
+        .subcommand(
+            SubCommand::with_name("debug")
+                .about("Build, package, install and debug with gdbserver")
+                .args(&tizen_env_args)
+                .arg(&serial_arg)
+                .arg(&app_arg)
+                .arg(&assume_yes_arg)
+                .arg(&release_arg)
+                .arg(&bin_arg)
+                .arg(
+                    Arg::with_name("port")
+                        .long("port")
+                        .takes_value(true)
+                        .default_value("26102")
+                        .help("Port of gdbserver, forwarded to the same local port"),
+                )
                 .arg(&forward_args),
         )
         .get_matches_from(get_os_args());
 
+    if let Some(name @ "new") | Some(name @ "init") = app_matches.subcommand_name() {
+        exit_with_result(commands::new::run(
+            app_matches.subcommand_matches(name).unwrap(),
+            name == "init",
+        ));
+    }
+
     let tizen_env = match app_matches.subcommand_name() {
         Some(sub_name) => match app_matches.subcommand_matches(sub_name) {
             Some(sub_matches) => {
-                match TizenEnv::from_cargo_config(&env::current_dir().unwrap(), &sub_matches) {
+                match TizenEnv::from_cargo_config(
+                    &env::current_dir().unwrap(),
+                    sub_matches,
+                    uses_target_triple(sub_name),
+                ) {
                     Ok(obj) => obj,
                     Err(message) => {
                         eprintln!("[ERROR] {}", message);
@@ -102,47 +377,106 @@ fn main() {
 
     let command_result = match app_matches.subcommand_name() {
         Some(name @ "config") => {
-            commands::config::run(&tizen_env, app_matches.subcommand_matches(&name).unwrap())
+            commands::config::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
         }
         Some(name @ "build") => {
-            commands::build::run(&tizen_env, app_matches.subcommand_matches(&name).unwrap())
+            commands::build::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
         }
         Some(name @ "package") => {
-            commands::package::run(&tizen_env, app_matches.subcommand_matches(&name).unwrap())
+            commands::package::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
         }
         Some(name @ "install") => {
-            commands::install::run(&tizen_env, app_matches.subcommand_matches(&name).unwrap())
+            commands::install::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
         }
         Some(name @ "run") => {
-            commands::run::run(&tizen_env, app_matches.subcommand_matches(&name).unwrap())
+            commands::run::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
+        }
+        Some(name @ "test") => {
+            commands::test::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
+        }
+        Some(name @ "exec") => {
+            commands::exec::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
         }
         Some(name @ "clean") => {
-            commands::clean::run(&tizen_env, app_matches.subcommand_matches(&name).unwrap())
+            commands::clean::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
+        }
+        Some(name @ "check") => {
+            commands::check::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
+        }
+        Some(name @ "log") => {
+            commands::log::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
+        }
+        Some(name @ "manifest") => {
+            commands::manifest::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
+        }
+        Some(name @ "devices") => {
+            commands::devices::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
+        }
+        Some(name @ "emulator") => {
+            commands::emulator::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
+        }
+        Some(name @ "i18n") => {
+            commands::i18n::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
+        }
+        Some(name @ "profiles") => {
+            commands::profiles::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
         }
         Some(name @ "dev") => {
-            commands::dev::run(&tizen_env, app_matches.subcommand_matches(&name).unwrap())
+            commands::dev::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
+        }
+        Some(name @ "debug") => {
+            commands::debug::run(&tizen_env, app_matches.subcommand_matches(name).unwrap())
         }
         _ => Err(TizenError {
             message: "No command matched!".to_string(),
         }),
     };
 
+    exit_with_result(command_result);
+}
+
+fn exit_with_result(command_result: Result<i32, TizenError>) -> ! {
     match command_result {
         Ok(exit_status) => process::exit(exit_status),
         Err(tizen_error) => {
             eprintln!("{}", &tizen_error.message.bold().red());
             process::exit(1);
         }
-    };
+    }
 }
 
-fn make_tizen_env_args<'a>() -> [Arg<'a, 'a>; 1] {
-    [Arg::with_name("emulator")
-        .short("e")
-        .long("emulator")
-        .required(false)
-        .takes_value(false)
-        .help("Compile to the emulator architecture")]
+// Commands building for the target or showing its config, the others only talk to
+// sdb or edit files and don't need is_emulator = "auto" to be detected
+fn uses_target_triple(command_name: &str) -> bool {
+    matches!(
+        command_name,
+        "build"
+            | "package"
+            | "install"
+            | "test"
+            | "clean"
+            | "config"
+            | "profiles"
+            | "dev"
+            | "debug"
+    )
+}
+
+fn make_tizen_env_args<'a>() -> [Arg<'a, 'a>; 2] {
+    [
+        Arg::with_name("emulator")
+            .short("e")
+            .long("emulator")
+            .required(false)
+            .takes_value(false)
+            .help("Compile to the emulator architecture"),
+        Arg::with_name("package")
+            .short("p")
+            .long("package")
+            .required(false)
+            .takes_value(true)
+            .help("Package of the workspace to use"),
+    ]
 }
 
 fn make_forward_arg<'a>() -> Arg<'a, 'a> {
@@ -151,13 +485,15 @@ fn make_forward_arg<'a>() -> Arg<'a, 'a> {
         .help("Forward the args")
 }
 
+// Packaging no longer asks anything, -y is still accepted for the existing scripts
 fn make_assume_yes_arg<'a>() -> Arg<'a, 'a> {
     Arg::with_name("assumeyes")
         .short("y")
         .long("assumeyes")
         .multiple(false)
         .required(false)
-        .help("Answer yes for all questions")
+        .hidden(true)
+        .help("Deprecated, does nothing")
 }
 
 fn make_release_arg<'a>() -> Arg<'a, 'a> {
@@ -169,6 +505,94 @@ fn make_release_arg<'a>() -> Arg<'a, 'a> {
         .help("Build release")
 }
 
+fn make_bin_arg<'a>() -> Arg<'a, 'a> {
+    Arg::with_name("bin")
+        .long("bin")
+        .takes_value(true)
+        .required(false)
+        .help("Binary to package (defaults to the manifest exec)")
+}
+
+fn make_app_arg<'a>() -> Arg<'a, 'a> {
+    Arg::with_name("app")
+        .long("app")
+        .takes_value(true)
+        .required(false)
+        .help("Application id to launch (defaults to the first ui-application)")
+}
+
+fn make_emulator_name_arg<'a>(required: bool) -> Arg<'a, 'a> {
+    Arg::with_name("name")
+        .required(required)
+        .help("Emulator name (defaults to name in [tizen.emulator])")
+}
+
+fn make_log_arg<'a>() -> Arg<'a, 'a> {
+    Arg::with_name("log")
+        .long("log")
+        .required(false)
+        .help("Stream the app logs after launching it")
+}
+
+fn make_serial_arg<'a>() -> Arg<'a, 'a> {
+    Arg::with_name("serial")
+        .short("s")
+        .long("serial")
+        .takes_value(true)
+        .required(false)
+        .help("Serial of the target device or emulator (see cargo tizen devices)")
+}
+
+fn make_privilege_arg<'a>() -> Arg<'a, 'a> {
+    Arg::with_name("uri")
+        .required(true)
+        .multiple(true)
+        .help("Privilege (e.g. http://tizen.org/privilege/internet)")
+}
+
+fn make_new_args<'a>() -> Vec<Arg<'a, 'a>> {
+    vec![
+        Arg::with_name("name")
+            .long("name")
+            .takes_value(true)
+            .help("Cargo package name (defaults to the folder name)"),
+        Arg::with_name("template")
+            .long("template")
+            .takes_value(true)
+            .possible_values(&["ui", "service", "watch"])
+            .default_value("ui")
+            .help("Kind of application"),
+        Arg::with_name("package_id")
+            .long("package-id")
+            .takes_value(true)
+            .help("Tizen package id (defaults to org.example.NAME)"),
+        Arg::with_name("app_id")
+            .long("app-id")
+            .takes_value(true)
+            .help("Tizen application id (defaults to the package id)"),
+        Arg::with_name("api_version")
+            .long("api-version")
+            .takes_value(true)
+            .default_value("5.5")
+            .help("Tizen api version"),
+        Arg::with_name("profile")
+            .long("profile")
+            .takes_value(true)
+            .default_value("wearable")
+            .help("Tizen profile"),
+        Arg::with_name("privilege")
+            .long("privilege")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Privilege to request, can be repeated"),
+        Arg::with_name("studio_path")
+            .long("studio-path")
+            .takes_value(true)
+            .help("Path to tizen studio (defaults to ~/tizen-studio)"),
+    ]
+}
+
 fn get_os_args() -> Vec<String> {
     let mut args: Vec<String> = vec![];
     let mut pos = 0;
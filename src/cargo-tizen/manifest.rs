@@ -0,0 +1,1304 @@
+use crate::error::TizenError;
+use crate::tizen_env::{ApplicationType, TizenEnv};
+use colored::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use sxd_document::dom::Element;
+use sxd_document::parser;
+use sxd_xpath::{Context, Factory, Value};
+use toml::Value as TomlValue;
+
+pub const MANIFEST_NS: &str = "http://tizen.org/ns/packages";
+
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: usize,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        matches!(self.severity, Severity::Error)
+    }
+
+    pub fn print(&self, file_name: &str) {
+        let severity = match self.severity {
+            Severity::Error => "error".red().bold(),
+            Severity::Warning => "warning".yellow().bold(),
+        };
+
+        println!(
+            "{}:{}: {}: {}",
+            file_name.bold(),
+            self.line,
+            severity,
+            &self.message
+        );
+    }
+}
+
+macro_rules! manifest_template {
+    ($name:expr) => {
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/res/templates/",
+            $name
+        ))
+    };
+}
+
+pub struct ManifestValues {
+    pub app_type: String,
+    pub api_version: String,
+    pub profile: String,
+    pub package: String,
+    pub version: String,
+    pub app_id: String,
+    pub exec: String,
+    pub label: String,
+    pub icon: Option<String>,
+    pub privileges: Vec<String>,
+    pub features: Vec<(String, String)>,
+}
+
+impl ManifestValues {
+    pub fn render(&self) -> Result<String, TizenError> {
+        let template = match self.app_type.as_str() {
+            "ui" => manifest_template!("ui-manifest.xml"),
+            "service" => manifest_template!("service-manifest.xml"),
+            "watch" => manifest_template!("watch-manifest.xml"),
+            other => {
+                return Err(TizenError {
+                    message: format!(
+                        "Unknown application type {}, use ui, service or watch",
+                        other
+                    ),
+                })
+            }
+        };
+
+        let mut extra = String::new();
+
+        if !self.privileges.is_empty() {
+            extra.push_str("    <privileges>\n");
+            for privilege in self.privileges.iter() {
+                extra.push_str(&format!(
+                    "        <privilege>{}</privilege>\n",
+                    escape_xml(privilege)
+                ));
+            }
+            extra.push_str("    </privileges>\n");
+        }
+
+        for (name, value) in self.features.iter() {
+            extra.push_str(&format!(
+                "    <feature name=\"{}\">{}</feature>\n",
+                escape_xml(name),
+                escape_xml(value)
+            ));
+        }
+
+        Ok(template
+            .split_inclusive('\n')
+            .filter(|line| self.icon.is_some() || !line.contains("{{icon}}"))
+            .collect::<String>()
+            .replace("{{api_version}}", &escape_xml(&self.api_version))
+            .replace("{{profile}}", &escape_xml(&self.profile))
+            .replace("{{package}}", &escape_xml(&self.package))
+            .replace("{{version}}", &escape_xml(&self.version))
+            .replace("{{app_id}}", &escape_xml(&self.app_id))
+            .replace("{{exec}}", &escape_xml(&self.exec))
+            .replace("{{label}}", &escape_xml(&self.label))
+            .replace(
+                "{{icon}}",
+                &escape_xml(self.icon.as_deref().unwrap_or_default()),
+            )
+            .replace("{{privileges}}", &extra))
+    }
+}
+
+// Builds the manifest from [package.metadata.tizen], None when the section is missing
+pub fn generate_manifest(
+    cargo_toml: &TomlValue,
+    cargo_version: Option<&str>,
+) -> Result<Option<String>, TizenError> {
+    let package = match cargo_toml.get("package") {
+        Some(package) => package,
+        None => return Ok(None),
+    };
+
+    let metadata = match package.get("metadata").and_then(|m| m.get("tizen")) {
+        Some(metadata) => metadata,
+        None => return Ok(None),
+    };
+
+    let get_str = |value: &TomlValue, key: &str| -> Option<String> {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string())
+    };
+
+    let app_id = match get_str(metadata, "app_id") {
+        Some(app_id) => app_id,
+        None => {
+            return Err(TizenError {
+                message: "Missing app_id in [package.metadata.tizen] of Cargo.toml".to_string(),
+            })
+        }
+    };
+    let name = get_str(package, "name").unwrap_or_default();
+
+    let values = ManifestValues {
+        app_type: get_str(metadata, "type").unwrap_or_else(|| "ui".to_string()),
+        api_version: get_str(metadata, "api_version").unwrap_or_else(|| "5.5".to_string()),
+        profile: get_str(metadata, "profile").unwrap_or_else(|| "wearable".to_string()),
+        package: get_str(metadata, "package").unwrap_or_else(|| app_id.clone()),
+        version: match cargo_version {
+            Some(version) => tizen_version(version).unwrap_or_else(|_| version.to_string()),
+            None => "1.0.0".to_string(),
+        },
+        exec: get_str(metadata, "exec").unwrap_or_else(|| name.clone()),
+        label: get_str(metadata, "label").unwrap_or(name),
+        icon: get_str(metadata, "icon"),
+        privileges: metadata
+            .get("privileges")
+            .and_then(|p| p.as_array())
+            .map(|p| {
+                p.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|v| v.to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        features: metadata
+            .get("features")
+            .and_then(|f| f.as_table())
+            .map(|f| {
+                f.iter()
+                    .map(|(name, value)| {
+                        let value = match value.as_str() {
+                            Some(str_value) => str_value.to_string(),
+                            None => value.to_string(),
+                        };
+                        (name.clone(), value)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        app_id,
+    };
+
+    values.render().map(Some)
+}
+
+pub fn manifest_path(base_path: &Path) -> PathBuf {
+    let mut path = PathBuf::from(base_path);
+    path.push("tizen-manifest.xml");
+
+    path
+}
+
+// sxd_document does not keep positions, so lines are found by scanning the raw
+// text in document order, the same order the elements are visited
+struct Locator<'c> {
+    content: &'c str,
+    cursor: usize,
+}
+
+impl<'c> Locator<'c> {
+    fn find_element(&mut self, name: &str) -> usize {
+        let mut pos = self.cursor;
+
+        while let Some(start) = next_tag(self.content, pos) {
+            let tag = tag_name(self.content, start);
+
+            if tag.rsplit(':').next() == Some(name) {
+                self.cursor = start;
+                return self.line_at(start);
+            }
+
+            pos = start;
+        }
+
+        self.line_at(self.cursor)
+    }
+
+    fn find_attribute(&self, name: &str) -> usize {
+        match attribute_value_span(self.content, self.cursor, name) {
+            Some((value_start, _)) => self.line_at(value_start),
+            None => self.line_at(self.cursor),
+        }
+    }
+
+    fn line_at(&self, pos: usize) -> usize {
+        self.content[..pos].matches('\n').count() + 1
+    }
+}
+
+struct Checker<'c> {
+    locator: Locator<'c>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'c> Checker<'c> {
+    fn error(&mut self, line: usize, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            line,
+            message,
+        });
+    }
+
+    fn warning(&mut self, line: usize, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            line,
+            message,
+        });
+    }
+
+    fn required_attribute<'d>(
+        &mut self,
+        element: &Element<'d>,
+        name: &str,
+        element_line: usize,
+    ) -> Option<(&'d str, usize)> {
+        match element.attribute_value(name) {
+            Some(value) if !value.trim().is_empty() => {
+                Some((value, self.locator.find_attribute(name)))
+            }
+            _ => {
+                self.error(
+                    element_line,
+                    format!(
+                        "<{}> is missing the required attribute '{}'",
+                        element.name().local_part(),
+                        name
+                    ),
+                );
+                None
+            }
+        }
+    }
+}
+
+pub fn check_manifest(
+    tizen_env: &TizenEnv,
+    bin_names: Option<&[String]>,
+) -> Result<Vec<Diagnostic>, TizenError> {
+    let content = tizen_env.manifest_content()?;
+    let package = parser::parse(&content)?;
+    let document = package.as_document();
+
+    let mut checker = Checker {
+        locator: Locator {
+            content: &content,
+            cursor: 0,
+        },
+        diagnostics: vec![],
+    };
+
+    let root = match document
+        .root()
+        .children()
+        .into_iter()
+        .find_map(|c| c.element())
+    {
+        Some(root) => root,
+        None => {
+            checker.error(1, "No root element found".to_string());
+            return Ok(checker.diagnostics);
+        }
+    };
+
+    let root_line = checker.locator.find_element(root.name().local_part());
+
+    if root.name().local_part() != "manifest" || root.name().namespace_uri() != Some(MANIFEST_NS) {
+        checker.error(
+            root_line,
+            format!("Root element must be <manifest xmlns=\"{}\">", MANIFEST_NS),
+        );
+        return Ok(checker.diagnostics);
+    }
+
+    let package_id = checker.required_attribute(&root, "package", root_line);
+    if let Some((package_id, line)) = package_id {
+        if !is_valid_package_id(package_id) {
+            checker.error(
+                line,
+                format!(
+                    "Invalid package '{}', use at least two dot separated parts starting with a letter (e.g. org.example.app)",
+                    package_id
+                ),
+            );
+        }
+    }
+
+    if let Some((version, line)) = checker.required_attribute(&root, "version", root_line) {
+        if !is_valid_version(version) {
+            checker.error(
+                line,
+                format!(
+                    "Invalid version '{}', expected x.y.z with x, y in 0-255 and z in 0-65535",
+                    version
+                ),
+            );
+        }
+
+        check_version_sync(&mut checker, tizen_env, version, line);
+    }
+
+    if let Some((api_version, line)) = checker.required_attribute(&root, "api-version", root_line) {
+        if !tizen_env.rootstrap_path.exists() {
+            checker.error(
+                line,
+                format!(
+                    "No rootstrap for api-version {} and profile {} ({} not found), installed platforms: {}",
+                    api_version,
+                    &tizen_env.app_profile,
+                    tizen_env.rootstrap_path.to_str().unwrap_or(""),
+                    installed_platforms(&tizen_env.studio_path).join(", ")
+                ),
+            );
+        }
+    }
+
+    let mut app_ids: Vec<String> = vec![];
+    let mut has_profile = false;
+
+    for element in root.children().into_iter().filter_map(|c| c.element()) {
+        let element_name = element.name().local_part();
+        let element_line = checker.locator.find_element(element_name);
+
+        if element_name == "profile" {
+            has_profile = true;
+            checker.required_attribute(&element, "name", element_line);
+            continue;
+        }
+
+        if element_name == "privileges" {
+            check_privileges(&mut checker, &element);
+            continue;
+        }
+
+        if !element_name.ends_with("-application") {
+            skip_descendants(&mut checker, &element);
+            continue;
+        }
+
+        if let ApplicationType::Unsupported(_) = ApplicationType::from_element(element_name) {
+            checker.error(
+                element_line,
+                format!("Unsupported application type <{}>", element_name),
+            );
+        }
+
+        if let Some((app_id, line)) = checker.required_attribute(&element, "appid", element_line) {
+            if !is_valid_app_id(app_id) {
+                checker.error(
+                    line,
+                    format!(
+                        "Invalid appid '{}', use only letters, digits, '.', '_' and '-'",
+                        app_id
+                    ),
+                );
+            } else if app_ids.iter().any(|a| a == app_id) {
+                checker.error(line, format!("Duplicated appid '{}'", app_id));
+            } else if let Some((package_id, _)) = package_id {
+                if !app_id.starts_with(package_id) {
+                    checker.warning(
+                        line,
+                        format!(
+                            "appid '{}' should start with the package '{}'",
+                            app_id, package_id
+                        ),
+                    );
+                }
+            }
+
+            app_ids.push(app_id.to_string());
+        }
+
+        if let Some((exec, line)) = checker.required_attribute(&element, "exec", element_line) {
+            if let Some(bin_names) = bin_names {
+                let exec_name = Path::new(exec)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+
+                // A single application can be packaged with another binary, renamed like its exec
+                if !bin_names.iter().any(|name| name == &exec_name) {
+                    let message = format!(
+                        "exec '{}' does not match a cargo binary, available binaries: {}",
+                        exec,
+                        bin_names.join(", ")
+                    );
+
+                    match tizen_env.applications.len() > 1 {
+                        true => checker.error(line, message),
+                        false => checker.warning(line, message),
+                    }
+                }
+            }
+        }
+
+        check_application_children(&mut checker, tizen_env, &element, element_line);
+    }
+
+    if !has_profile {
+        checker.warning(
+            root_line,
+            "No <profile> found, the app profile will come from the cargo config".to_string(),
+        );
+    }
+
+    if app_ids.is_empty() {
+        checker.error(root_line, "No application found".to_string());
+    }
+
+    Ok(checker.diagnostics)
+}
+
+fn check_version_sync(checker: &mut Checker, tizen_env: &TizenEnv, version: &str, line: usize) {
+    if tizen_env.version_sync == "off" || tizen_env.cargo_version.is_empty() {
+        return;
+    }
+
+    let cargo_version = match tizen_version(&tizen_env.cargo_version) {
+        Ok(cargo_version) => cargo_version,
+        Err(error) => {
+            match tizen_env.version_sync.as_str() {
+                "warn" => checker.warning(line, error.message),
+                _ => checker.error(line, error.message),
+            }
+            return;
+        }
+    };
+
+    if cargo_version == version {
+        return;
+    }
+
+    let message = format!(
+        "version {} differs from the Cargo.toml version {}",
+        version, &tizen_env.cargo_version
+    );
+
+    match tizen_env.version_sync.as_str() {
+        "warn" => checker.warning(line, message),
+        "fail" => checker.error(line, message),
+        "rewrite" => {}
+        other => checker.error(
+            line,
+            format!(
+                "Unsupported version_sync {}, use off, warn, fail or rewrite",
+                other
+            ),
+        ),
+    }
+}
+
+fn check_application_children(
+    checker: &mut Checker,
+    tizen_env: &TizenEnv,
+    application: &Element,
+    application_line: usize,
+) {
+    let mut has_label = false;
+
+    for child in application
+        .children()
+        .into_iter()
+        .filter_map(|c| c.element())
+    {
+        let child_name = child.name().local_part();
+        let child_line = checker.locator.find_element(child_name);
+
+        match child_name {
+            "label" => has_label = true,
+            "icon" => {
+                let icon = element_text(&child);
+                let mut icon_path = tizen_env.base_path.clone();
+                icon_path.push("shared");
+                icon_path.push("res");
+                icon_path.push(icon.trim());
+
+                if icon.trim().is_empty() {
+                    checker.error(child_line, "Empty <icon>".to_string());
+                } else if !icon_path.is_file() {
+                    checker.error(
+                        child_line,
+                        format!("Icon file not found {}", icon_path.to_str().unwrap_or("")),
+                    );
+                }
+            }
+            _ => skip_descendants(checker, &child),
+        }
+    }
+
+    if !has_label {
+        checker.warning(
+            application_line,
+            format!("<{}> has no <label>", application.name().local_part()),
+        );
+    }
+}
+
+fn check_privileges(checker: &mut Checker, privileges: &Element) {
+    for privilege in privileges
+        .children()
+        .into_iter()
+        .filter_map(|c| c.element())
+    {
+        let line = checker.locator.find_element(privilege.name().local_part());
+        let value = element_text(&privilege);
+
+        if !value.trim().starts_with("http://tizen.org/privilege/") {
+            checker.warning(line, format!("Unknown privilege '{}'", value.trim()));
+        }
+    }
+}
+
+fn skip_descendants(checker: &mut Checker, element: &Element) {
+    for child in element.children().into_iter().filter_map(|c| c.element()) {
+        checker.locator.find_element(child.name().local_part());
+        skip_descendants(checker, &child);
+    }
+}
+
+fn element_text(element: &Element) -> String {
+    element
+        .children()
+        .into_iter()
+        .filter_map(|c| c.text())
+        .map(|t| t.text().to_string())
+        .collect()
+}
+
+fn installed_platforms(studio_path: &Path) -> Vec<String> {
+    let mut platforms_path = PathBuf::from(studio_path);
+    platforms_path.push("platforms");
+
+    let mut platforms: Vec<String> = match fs::read_dir(platforms_path) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().to_str().map(|s| s.to_string()))
+            .filter_map(|name| name.strip_prefix("tizen-").map(|s| s.to_string()))
+            .collect(),
+        Err(_) => vec![],
+    };
+
+    platforms.sort();
+    platforms
+}
+
+pub fn is_valid_package_id(package_id: &str) -> bool {
+    let parts: Vec<&str> = package_id.split('.').collect();
+
+    parts.len() >= 2
+        && parts.iter().all(|part| {
+            part.chars()
+                .next()
+                .map(|c| c.is_ascii_alphabetic())
+                .unwrap_or(false)
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+fn is_valid_app_id(app_id: &str) -> bool {
+    !app_id.is_empty()
+        && app_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-')
+}
+
+// Tizen versions are x.y.z numbers only, pre-release and build suffixes are dropped
+pub fn tizen_version(cargo_version: &str) -> Result<String, TizenError> {
+    let version = cargo_version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    match is_valid_version(&version) {
+        true => Ok(version),
+        false => Err(TizenError {
+            message: format!(
+                "Version {} can't be used in tizen-manifest.xml, expected x.y.z with x, y in 0-255 and z in 0-65535",
+                cargo_version
+            ),
+        }),
+    }
+}
+
+pub fn is_valid_version(version: &str) -> bool {
+    let parts: Vec<&str> = version.split('.').collect();
+    let limits = [255, 255, 65535];
+
+    parts.len() == 3
+        && parts.iter().zip(limits.iter()).all(|(part, limit)| {
+            !part.is_empty()
+                && part.chars().all(|c| c.is_ascii_digit())
+                && part.parse::<u32>().map(|n| n <= *limit).unwrap_or(false)
+        })
+}
+
+pub struct ManifestEditor {
+    path: PathBuf,
+    content: String,
+}
+
+impl ManifestEditor {
+    pub fn open(base_path: &Path) -> Result<Self, TizenError> {
+        let path = manifest_path(base_path);
+        let content = fs::read_to_string(&path)?;
+
+        Ok(Self { path, content })
+    }
+
+    pub fn from_content(base_path: &Path, content: &str) -> Self {
+        Self {
+            path: manifest_path(base_path),
+            content: content.to_string(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), TizenError> {
+        parser::parse(&self.content)?;
+        fs::write(&self.path, &self.content)?;
+
+        Ok(())
+    }
+
+    pub fn into_content(self) -> Result<String, TizenError> {
+        parser::parse(&self.content)?;
+
+        Ok(self.content)
+    }
+
+    pub fn exists(&self, xpath: &str) -> Result<bool, TizenError> {
+        Ok(self.find_element(xpath)?.is_some())
+    }
+
+    // Sets the attribute or the element text selected by a manifest key
+    pub fn set_value(&mut self, xpath: &str, value: &str) -> Result<(), TizenError> {
+        let (element_xpath, attribute) = match xpath.rsplit_once("/@") {
+            Some((element_xpath, attribute)) => (element_xpath, Some(attribute)),
+            None => (xpath, None),
+        };
+
+        let start = self.require_element(element_xpath)?;
+        let value = escape_xml(value);
+
+        match attribute {
+            Some(attribute) => match attribute_value_span(&self.content, start, attribute) {
+                Some((value_start, value_end)) => {
+                    self.content.replace_range(value_start..value_end, &value)
+                }
+                None => {
+                    let mut insert_at = start_tag_end(&self.content, start);
+                    if self.content[..insert_at].ends_with('/') {
+                        insert_at -= 1;
+                    }
+                    let trimmed = self.content[..insert_at].trim_end().len();
+                    self.content.replace_range(
+                        trimmed..insert_at,
+                        &format!(" {}=\"{}\"", attribute, value),
+                    );
+                }
+            },
+            None => match closing_tag_start(&self.content, start) {
+                Some(closing_start) => {
+                    let text_start = start_tag_end(&self.content, start) + 1;
+                    self.content
+                        .replace_range(text_start..closing_start, &value);
+                }
+                None => {
+                    let tag_end = start_tag_end(&self.content, start);
+                    let name = tag_name(&self.content, start);
+                    self.content
+                        .replace_range(tag_end - 1..tag_end + 1, &format!(">{}</{}>", value, name));
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    pub fn add_child(&mut self, parent_xpath: &str, child: &str) -> Result<(), TizenError> {
+        let start = self.require_element(parent_xpath)?;
+        let name = tag_name(&self.content, start);
+        // The child goes in the manifest namespace, with the prefix used by its parent
+        let child = match name.split_once(':') {
+            Some((prefix, _)) => prefix_element(child, prefix),
+            None => child.to_string(),
+        };
+        let parent_indent = line_indent(&self.content, start);
+
+        let closing_start = match closing_tag_start(&self.content, start) {
+            Some(closing_start) => closing_start,
+            None => {
+                let tag_end = start_tag_end(&self.content, start);
+                self.content
+                    .replace_range(tag_end - 1..tag_end + 1, &format!("></{}>", name));
+                tag_end
+            }
+        };
+        let tag_end = start_tag_end(&self.content, start);
+
+        let first_child = self.content[tag_end + 1..closing_start]
+            .find('<')
+            .map(|pos| tag_end + 1 + pos)
+            .filter(|pos| starts_line(&self.content, *pos));
+
+        let child_indent = match first_child {
+            Some(pos) => line_indent(&self.content, pos),
+            None if parent_indent.contains('\t') => format!("{}\t", &parent_indent),
+            None => format!("{}    ", &parent_indent),
+        };
+
+        if starts_line(&self.content, closing_start) {
+            let line_start = self.content[..closing_start]
+                .rfind('\n')
+                .map(|pos| pos + 1)
+                .unwrap_or(0);
+
+            self.content
+                .insert_str(line_start, &format!("{}{}\n", child_indent, child));
+        } else {
+            self.content.insert_str(
+                closing_start,
+                &format!("\n{}{}\n{}", child_indent, child, parent_indent),
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn remove_element(&mut self, xpath: &str) -> Result<(), TizenError> {
+        let start = self.require_element(xpath)? - 1;
+
+        let mut end = match closing_tag_start(&self.content, start + 1) {
+            Some(closing_start) => match self.content[closing_start..].find('>') {
+                Some(pos) => closing_start + pos + 1,
+                None => self.content.len(),
+            },
+            None => start_tag_end(&self.content, start + 1) + 1,
+        };
+
+        let mut start = start;
+        let line_start = self.content[..start]
+            .rfind('\n')
+            .map(|pos| pos + 1)
+            .unwrap_or(0);
+        let line_end = self.content[end..]
+            .find('\n')
+            .map(|pos| end + pos + 1)
+            .unwrap_or_else(|| self.content.len());
+
+        if self.content[line_start..start].trim().is_empty()
+            && self.content[end..line_end].trim().is_empty()
+        {
+            start = line_start;
+            end = line_end;
+        }
+
+        self.content.replace_range(start..end, "");
+
+        Ok(())
+    }
+
+    fn require_element(&self, xpath: &str) -> Result<usize, TizenError> {
+        match self.find_element(xpath)? {
+            Some(start) => Ok(start),
+            None => Err(TizenError {
+                message: format!("Can't find {} in tizen-manifest.xml", xpath),
+            }),
+        }
+    }
+
+    // Returns the offset just after the '<' of the first element matching the xpath
+    fn find_element(&self, xpath: &str) -> Result<Option<usize>, TizenError> {
+        let package = parser::parse(&self.content)?;
+        let document = package.as_document();
+
+        let expression = match Factory::new().build(xpath) {
+            Ok(Some(expression)) => expression,
+            _ => {
+                return Err(TizenError {
+                    message: format!("Invalid xpath {}", xpath),
+                })
+            }
+        };
+
+        let mut context = Context::new();
+        context.set_namespace("ns", MANIFEST_NS);
+
+        let target = match expression.evaluate(&context, document.root()) {
+            Ok(Value::Nodeset(nodes)) => nodes
+                .document_order()
+                .into_iter()
+                .find_map(|node| node.element()),
+            _ => None,
+        };
+
+        let target = match target {
+            Some(target) => target,
+            None => return Ok(None),
+        };
+
+        let mut locator = Locator {
+            content: &self.content,
+            cursor: 0,
+        };
+
+        Ok(document
+            .root()
+            .children()
+            .into_iter()
+            .filter_map(|c| c.element())
+            .find_map(|root| locate_element(&mut locator, root, target)))
+    }
+}
+
+fn locate_element(locator: &mut Locator, element: Element, target: Element) -> Option<usize> {
+    locator.find_element(element.name().local_part());
+
+    if element == target {
+        return Some(locator.cursor);
+    }
+
+    element
+        .children()
+        .into_iter()
+        .filter_map(|c| c.element())
+        .find_map(|child| locate_element(locator, child, target))
+}
+
+// Returns the offset just after the '<' of the next start or end tag, skipping the
+// comments, CDATA sections, processing instructions and doctype
+fn next_tag(content: &str, pos: usize) -> Option<usize> {
+    let mut pos = pos;
+
+    loop {
+        let start = pos + content[pos..].find('<')?;
+        let rest = &content[start..];
+
+        let (skipped, terminator) = if rest.starts_with("<!--") {
+            (4, "-->")
+        } else if rest.starts_with("<![CDATA[") {
+            (9, "]]>")
+        } else if rest.starts_with("<?") {
+            (2, "?>")
+        } else if rest.starts_with("<!") {
+            (2, ">")
+        } else {
+            return Some(start + 1);
+        };
+
+        let body_start = start + skipped;
+        pos = body_start + content[body_start..].find(terminator)? + terminator.len();
+    }
+}
+
+// Adds the prefix to the names of a single element like <name attr="">text</name>
+fn prefix_element(element: &str, prefix: &str) -> String {
+    let element = element.trim();
+    let name = tag_name(element, 1);
+    let closing = format!("</{}>", name);
+
+    let mut prefixed = format!("<{}:{}", prefix, &element[1..]);
+    if prefixed.ends_with(&closing) {
+        prefixed.truncate(prefixed.len() - closing.len());
+        prefixed.push_str(&format!("</{}:{}>", prefix, name));
+    }
+
+    prefixed
+}
+
+fn tag_name(content: &str, start: usize) -> String {
+    content[start..]
+        .chars()
+        .take_while(|c| !c.is_whitespace() && *c != '>' && *c != '/')
+        .collect()
+}
+
+fn start_tag_end(content: &str, start: usize) -> usize {
+    let mut quote: Option<char> = None;
+
+    for (pos, c) in content[start..].char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return start + pos,
+            _ => {}
+        }
+    }
+
+    content.len()
+}
+
+fn closing_tag_start(content: &str, start: usize) -> Option<usize> {
+    let tag_end = start_tag_end(content, start);
+    if content[..tag_end].ends_with('/') {
+        return None;
+    }
+
+    let name = tag_name(content, start);
+    let local_name = name.rsplit(':').next().unwrap_or("").to_string();
+    let mut depth = 0;
+    let mut pos = tag_end;
+
+    while let Some(tag_start) = next_tag(content, pos) {
+        let is_closing = content[tag_start..].starts_with('/');
+        let found_name = tag_name(content, if is_closing { tag_start + 1 } else { tag_start });
+        let matches = found_name.rsplit(':').next() == Some(local_name.as_str());
+        let end = start_tag_end(content, tag_start);
+
+        if matches && is_closing {
+            if depth == 0 {
+                return Some(tag_start - 1);
+            }
+            depth -= 1;
+        } else if matches && !content[..end].ends_with('/') {
+            depth += 1;
+        }
+
+        pos = end;
+    }
+
+    None
+}
+
+fn attribute_value_span(content: &str, start: usize, name: &str) -> Option<(usize, usize)> {
+    let tag = &content[start..start_tag_end(content, start)];
+
+    let (index, _) = tag.match_indices(name).find(|(index, _)| {
+        let before = tag[..*index].chars().last();
+        let after = tag[index + name.len()..].trim_start();
+
+        before.map(|c| c.is_whitespace()).unwrap_or(false) && after.starts_with('=')
+    })?;
+
+    let after_name = &tag[index + name.len()..];
+    let quote_pos = after_name.find(['"', '\''])?;
+    let quote = after_name[quote_pos..].chars().next()?;
+    let value_start = index + name.len() + quote_pos + 1;
+    let value_end = value_start + tag[value_start..].find(quote)?;
+
+    Some((start + value_start, start + value_end))
+}
+
+fn starts_line(content: &str, pos: usize) -> bool {
+    let line_start = content[..pos].rfind('\n').map(|p| p + 1).unwrap_or(0);
+
+    content[line_start..pos].trim().is_empty()
+}
+
+fn line_indent(content: &str, pos: usize) -> String {
+    let line_start = content[..pos].rfind('\n').map(|p| p + 1).unwrap_or(0);
+
+    content[line_start..]
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect()
+}
+
+pub fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::App;
+
+    fn check(manifest: &str, bin_names: &[&str]) -> Vec<(bool, usize, String)> {
+        check_generated(manifest, None, bin_names)
+    }
+
+    // The env is read from the manifest file, the generated one is checked instead when given
+    fn check_generated(
+        manifest: &str,
+        generated: Option<&str>,
+        bin_names: &[&str],
+    ) -> Vec<(bool, usize, String)> {
+        let project = tempfile::tempdir().unwrap();
+        fs::write(
+            project.path().join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"1.0.0\"\n",
+        )
+        .unwrap();
+        fs::write(manifest_path(project.path()), manifest).unwrap();
+        fs::create_dir(project.path().join(".cargo")).unwrap();
+        fs::write(
+            project.path().join(".cargo/config.toml"),
+            "[tizen]\nstudio_path = \"/nonexistent/tizen-studio\"\nis_emulator = false\napi_version = \"5.5\"\napp_profile = \"wearable\"\ntoolchain = \"llvm-10\"\n",
+        )
+        .unwrap();
+
+        let args = App::new("cargo-tizen").get_matches_from(vec!["cargo-tizen"]);
+        let mut tizen_env = TizenEnv::from_cargo_config(project.path(), &args, false).unwrap();
+        tizen_env.generated_manifest = generated.map(|content| content.to_string());
+        let bin_names: Vec<String> = bin_names.iter().map(|name| name.to_string()).collect();
+
+        check_manifest(&tizen_env, Some(&bin_names))
+            .unwrap()
+            .into_iter()
+            .filter(|d| !d.message.starts_with("No rootstrap"))
+            .map(|d| (d.is_error(), d.line, d.message))
+            .collect()
+    }
+
+    #[test]
+    fn checker_reports_the_lines_after_comments() {
+        let diagnostics = check(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<manifest xmlns="http://tizen.org/ns/packages" api-version="5.5" package="org.example" version="1.0.0">
+    <profile name="wearable"/>
+    <!--
+    <ui-application appid="org.example.old" exec="old">
+    </ui-application>
+    -->
+    <ui-application appid="org.example.app" exec="app">
+        <label>App</label>
+    </ui-application>
+    <service-application appid="org.example.app"
+                         exec="worker">
+    </service-application>
+</manifest>
+"#,
+            &["app"],
+        );
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                (true, 11, "Duplicated appid 'org.example.app'".to_string()),
+                (
+                    true,
+                    12,
+                    "exec 'worker' does not match a cargo binary, available binaries: app"
+                        .to_string()
+                ),
+                (
+                    false,
+                    11,
+                    "<service-application> has no <label>".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn checker_reports_invalid_attributes() {
+        let diagnostics = check(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<manifest xmlns="http://tizen.org/ns/packages" api-version="5.5" package="example" version="1.0">
+    <ui-application appid="other.app" exec="main">
+        <label>App</label>
+        <icon></icon>
+    </ui-application>
+    <privileges>
+        <privilege>internet</privilege>
+    </privileges>
+</manifest>
+"#,
+            &["app"],
+        );
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                (true, 2, "Invalid package 'example', use at least two dot separated parts starting with a letter (e.g. org.example.app)".to_string()),
+                (true, 2, "Invalid version '1.0', expected x.y.z with x, y in 0-255 and z in 0-65535".to_string()),
+                (false, 2, "version 1.0 differs from the Cargo.toml version 1.0.0".to_string()),
+                (false, 3, "appid 'other.app' should start with the package 'example'".to_string()),
+                (false, 3, "exec 'main' does not match a cargo binary, available binaries: app".to_string()),
+                (true, 5, "Empty <icon>".to_string()),
+                (false, 8, "Unknown privilege 'internet'".to_string()),
+                (false, 2, "No <profile> found, the app profile will come from the cargo config".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn checker_requires_the_manifest_root() {
+        let diagnostics = check_generated(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<manifest xmlns="http://tizen.org/ns/packages" api-version="5.5" package="org.example" version="1.0.0">
+    <ui-application appid="org.example.app" exec="app">
+        <label>App</label>
+    </ui-application>
+</manifest>
+"#,
+            Some(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<!-- <manifest xmlns="http://tizen.org/ns/packages"> -->
+<package>
+    <ui-application appid="org.example.app" exec="app"/>
+</package>
+"#,
+            ),
+            &["app"],
+        );
+
+        assert_eq!(
+            diagnostics,
+            vec![(
+                true,
+                3,
+                "Root element must be <manifest xmlns=\"http://tizen.org/ns/packages\">"
+                    .to_string()
+            )]
+        );
+    }
+
+    fn edit(content: &str, change: impl Fn(&mut ManifestEditor)) -> String {
+        let mut editor = ManifestEditor::from_content(Path::new("/tmp"), content);
+        change(&mut editor);
+        editor.into_content().unwrap()
+    }
+
+    const COMMENTED: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<!-- <manifest package="org.old"> kept as reference -->
+<manifest xmlns="http://tizen.org/ns/packages" api-version="5.5" package="org.example" version="1.0.0">
+    <!-- <ui-application appid="org.example.old" exec="old"></ui-application> -->
+    <ui-application appid="org.example.app" exec="app" type="capp">
+        <label>App</label>
+        <icon/>
+    </ui-application>
+    <privileges>
+        <!-- </privileges> -->
+        <privilege><![CDATA[http://tizen.org/privilege/internet]]></privilege>
+    </privileges>
+</manifest>
+"#;
+
+    #[test]
+    fn set_value_skips_comments() {
+        let content = edit(COMMENTED, |editor| {
+            editor
+                .set_value("/ns:manifest/ns:ui-application/@appid", "org.example.new")
+                .unwrap();
+            editor
+                .set_value("/ns:manifest/@package", "org.new")
+                .unwrap();
+        });
+
+        assert!(content.contains(r#"<ui-application appid="org.example.new" exec="app""#));
+        assert!(content.contains(r#"<ui-application appid="org.example.old" exec="old">"#));
+        assert!(content.contains(r#"package="org.new" version="1.0.0">"#));
+        assert!(content.contains(r#"<manifest package="org.old"> kept"#));
+    }
+
+    #[test]
+    fn set_value_fills_self_closing_elements() {
+        let content = edit(COMMENTED, |editor| {
+            editor
+                .set_value("/ns:manifest/ns:ui-application/ns:icon", "app.png")
+                .unwrap();
+            editor
+                .set_value("/ns:manifest/ns:ui-application/ns:icon/@size", "64")
+                .unwrap();
+        });
+
+        assert!(content.contains("        <icon size=\"64\">app.png</icon>\n"));
+    }
+
+    #[test]
+    fn add_child_skips_comments() {
+        let content = edit(COMMENTED, |editor| {
+            editor
+                .add_child(
+                    "/ns:manifest/ns:privileges",
+                    "<privilege>http://tizen.org/privilege/network.get</privilege>",
+                )
+                .unwrap();
+        });
+
+        assert!(content.contains(
+            "]]></privilege>\n        <privilege>http://tizen.org/privilege/network.get</privilege>\n    </privileges>\n"
+        ));
+        assert!(content.contains("<!-- </privileges> -->"));
+    }
+
+    #[test]
+    fn add_child_opens_self_closing_elements() {
+        let content = edit(COMMENTED, |editor| {
+            editor
+                .add_child("/ns:manifest/ns:ui-application/ns:icon", "<small/>")
+                .unwrap();
+        });
+
+        assert!(content.contains("        <icon>\n            <small/>\n        </icon>\n"));
+    }
+
+    #[test]
+    fn remove_element_skips_comments_and_cdata() {
+        let content = edit(COMMENTED, |editor| {
+            editor.remove_element("/ns:manifest/ns:privileges").unwrap();
+            editor
+                .remove_element("/ns:manifest/ns:ui-application/ns:icon")
+                .unwrap();
+        });
+
+        assert!(!content.contains("<privileges>"));
+        assert!(!content.contains("<icon"));
+        assert!(
+            content.contains("        <label>App</label>\n    </ui-application>\n</manifest>\n")
+        );
+        assert!(content.contains("<!-- <ui-application"));
+    }
+
+    const PREFIXED: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<t:manifest xmlns:t="http://tizen.org/ns/packages" package="org.example" version="1.0.0">
+    <t:ui-application appid="org.example.app" exec="app">
+        <t:label>App</t:label>
+    </t:ui-application>
+    <t:privileges/>
+</t:manifest>
+"#;
+
+    #[test]
+    fn edits_prefixed_manifests() {
+        let content = edit(PREFIXED, |editor| {
+            editor
+                .add_child(
+                    "/ns:manifest/ns:privileges",
+                    "<privilege>http://tizen.org/privilege/internet</privilege>",
+                )
+                .unwrap();
+            editor
+                .set_value("/ns:manifest/ns:ui-application/ns:label", "Renamed")
+                .unwrap();
+            assert!(editor
+                .exists("/ns:manifest/ns:privileges/ns:privilege")
+                .unwrap());
+            editor
+                .remove_element("/ns:manifest/ns:ui-application")
+                .unwrap();
+        });
+
+        assert_eq!(
+            content,
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<t:manifest xmlns:t="http://tizen.org/ns/packages" package="org.example" version="1.0.0">
+    <t:privileges>
+        <t:privilege>http://tizen.org/privilege/internet</t:privilege>
+    </t:privileges>
+</t:manifest>
+"#
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use crate::error::TizenError;
+use std::fs;
+use std::path::{Path, PathBuf};
+use sxd_document::dom::Element;
+use sxd_document::parser;
+
+pub struct SecurityProfile {
+    pub name: String,
+    pub is_active: bool,
+    pub author: ProfileCert,
+    pub distributor: ProfileCert,
+}
+
+pub struct ProfileCert {
+    pub key: String,
+    pub password: String,
+    pub ca: String,
+}
+
+impl ProfileCert {
+    // Studio keeps the passwords encrypted in .pwd files next to the keys
+    pub fn has_plain_password(&self) -> bool {
+        !self.password.is_empty() && !Path::new(&self.password).is_file()
+    }
+}
+
+pub fn profiles_path(studio_path: &Path) -> PathBuf {
+    let mut path = PathBuf::from(studio_path);
+    path.pop();
+    path.push("tizen-studio-data");
+    path.push("profile");
+    path.push("profiles.xml");
+
+    path
+}
+
+pub fn read_profiles(studio_path: &Path) -> Result<Vec<SecurityProfile>, TizenError> {
+    let path = profiles_path(studio_path);
+
+    if !path.exists() {
+        return Err(TizenError {
+            message: format!(
+                "Security profiles file does not exists {}",
+                path.to_str().unwrap_or("")
+            ),
+        });
+    }
+
+    let package = parser::parse(&fs::read_to_string(&path)?)?;
+    let document = package.as_document();
+
+    let root = match document
+        .root()
+        .children()
+        .into_iter()
+        .find_map(|c| c.element())
+    {
+        Some(root) if root.name().local_part() == "profiles" => root,
+        _ => {
+            return Err(TizenError {
+                message: format!("Invalid profiles file {}", path.to_str().unwrap_or("")),
+            })
+        }
+    };
+
+    let active = root.attribute_value("active").unwrap_or("");
+
+    Ok(child_elements(root, "profile")
+        .into_iter()
+        .map(|profile| {
+            let items = child_elements(profile, "profileitem");
+            let name = profile.attribute_value("name").unwrap_or("").to_string();
+
+            SecurityProfile {
+                is_active: name == active,
+                author: profile_cert(&items, "0"),
+                distributor: profile_cert(&items, "1"),
+                name,
+            }
+        })
+        .collect())
+}
+
+pub fn find_profile(studio_path: &Path, name: &str) -> Result<SecurityProfile, TizenError> {
+    let profiles = read_profiles(studio_path)?;
+    let available: Vec<String> = profiles.iter().map(|p| p.name.clone()).collect();
+
+    match profiles.into_iter().find(|p| p.name == name) {
+        Some(profile) => Ok(profile),
+        None => Err(TizenError {
+            message: format!(
+                "Security profile '{}' not found, available profiles: {}",
+                name,
+                available.join(", ")
+            ),
+        }),
+    }
+}
+
+fn child_elements<'d>(parent: Element<'d>, name: &str) -> Vec<Element<'d>> {
+    parent
+        .children()
+        .into_iter()
+        .filter_map(|c| c.element())
+        .filter(|e| e.name().local_part() == name)
+        .collect()
+}
+
+fn profile_cert(items: &[Element], distributor: &str) -> ProfileCert {
+    let item = items
+        .iter()
+        .find(|e| e.attribute_value("distributor") == Some(distributor));
+
+    let attribute = |name: &str| {
+        item.and_then(|e| e.attribute_value(name))
+            .unwrap_or("")
+            .to_string()
+    };
+
+    ProfileCert {
+        key: attribute("key"),
+        password: attribute("password"),
+        ca: attribute("ca"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<profiles active="release" version="3.1">
+    <profile name="test">
+        <profileitem ca="" distributor="0" key="/keys/test/author.p12" password="secret" rootca=""/>
+        <profileitem ca="" distributor="1" key="" password="" rootca=""/>
+        <profileitem ca="" distributor="2" key="" password="" rootca=""/>
+    </profile>
+    <profile name="release">
+        <profileitem ca="/keys/author-ca.cer" distributor="0" key="/keys/release/author.p12" password="{author_pwd}" rootca=""/>
+        <profileitem ca="/keys/distributor-ca.cer" distributor="1" key="/keys/release/distributor.p12" password="{distributor_pwd}" rootca=""/>
+    </profile>
+    <profile name="partial">
+        <profileitem ca="" distributor="0" key="/keys/partial/author.p12" password="secret" rootca=""/>
+    </profile>
+</profiles>
+"#;
+
+    // Returns the studio path, with profiles.xml in the tizen-studio-data next to it
+    fn studio_with_profiles(root: &Path) -> PathBuf {
+        let keys_dir = root.join("keys");
+        fs::create_dir_all(&keys_dir).unwrap();
+        let author_pwd = keys_dir.join("author.pwd");
+        let distributor_pwd = keys_dir.join("distributor.pwd");
+        fs::write(&author_pwd, "encrypted").unwrap();
+        fs::write(&distributor_pwd, "encrypted").unwrap();
+
+        let studio_path = root.join("tizen-studio");
+        let path = profiles_path(&studio_path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            path,
+            PROFILES
+                .replace("{author_pwd}", author_pwd.to_str().unwrap())
+                .replace("{distributor_pwd}", distributor_pwd.to_str().unwrap()),
+        )
+        .unwrap();
+
+        studio_path
+    }
+
+    #[test]
+    fn profiles_are_read_with_the_active_one() {
+        let root = tempfile::tempdir().unwrap();
+        let profiles = read_profiles(&studio_with_profiles(root.path())).unwrap();
+
+        let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["test", "release", "partial"]);
+
+        let active: Vec<&str> = profiles
+            .iter()
+            .filter(|p| p.is_active)
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(active, vec!["release"]);
+    }
+
+    #[test]
+    fn profiles_have_the_author_and_distributor_keys() {
+        let root = tempfile::tempdir().unwrap();
+        let profile = find_profile(&studio_with_profiles(root.path()), "release").unwrap();
+
+        assert_eq!(profile.author.key, "/keys/release/author.p12");
+        assert_eq!(profile.author.ca, "/keys/author-ca.cer");
+        assert_eq!(profile.distributor.key, "/keys/release/distributor.p12");
+        assert_eq!(profile.distributor.ca, "/keys/distributor-ca.cer");
+    }
+
+    #[test]
+    fn pwd_files_are_not_plain_passwords() {
+        let root = tempfile::tempdir().unwrap();
+        let studio_path = studio_with_profiles(root.path());
+
+        let test = find_profile(&studio_path, "test").unwrap();
+        assert!(test.author.has_plain_password());
+        assert_eq!(test.author.password, "secret");
+
+        let release = find_profile(&studio_path, "release").unwrap();
+        assert!(!release.author.has_plain_password());
+        assert!(!release.distributor.has_plain_password());
+    }
+
+    #[test]
+    fn missing_distributors_are_empty() {
+        let root = tempfile::tempdir().unwrap();
+        let studio_path = studio_with_profiles(root.path());
+
+        for name in ["test", "partial"] {
+            let profile = find_profile(&studio_path, name).unwrap();
+            assert!(profile.distributor.key.is_empty());
+            assert!(!profile.distributor.has_plain_password());
+        }
+    }
+
+    #[test]
+    fn unknown_profiles_list_the_available_ones() {
+        let root = tempfile::tempdir().unwrap();
+        let result = find_profile(&studio_with_profiles(root.path()), "other");
+
+        assert_eq!(
+            result.err().map(|e| e.message),
+            Some(
+                "Security profile 'other' not found, available profiles: test, release, partial"
+                    .to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,679 @@
+use crate::error::TizenError;
+use crate::tizen_env::TizenEnv;
+use colored::*;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufRead, BufReader};
+use std::net::TcpStream;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const EXIT_MARKER: &str = "__CARGO_TIZEN_EXIT__:";
+const SYNC_CHUNK_SIZE: usize = 64 * 1024;
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub const REMOTE_TMP_DIR: &str = "/home/owner/share/tmp/sdk_tools/tmp";
+
+pub struct SdbDevice {
+    pub serial: String,
+    pub state: String,
+    pub name: String,
+}
+
+impl SdbDevice {
+    pub fn is_emulator(&self) -> bool {
+        self.serial.starts_with("emulator-")
+    }
+}
+
+pub struct SdbClient {
+    port: u16,
+    sdb_bin: String,
+    target_serial: String,
+    is_emulator: Option<bool>,
+    emulator_name: String,
+}
+
+impl SdbClient {
+    pub fn new(tizen_env: &TizenEnv) -> Self {
+        Self::with_config(
+            tizen_env.sdb_port,
+            &tizen_env.sdb_bin,
+            &tizen_env.target_serial,
+            Some(tizen_env.is_emulator),
+            &tizen_env.emulator_name,
+        )
+    }
+
+    pub fn with_config(
+        port: u16,
+        sdb_bin: &str,
+        target_serial: &str,
+        is_emulator: Option<bool>,
+        emulator_name: &str,
+    ) -> Self {
+        Self {
+            port,
+            sdb_bin: sdb_bin.to_string(),
+            target_serial: target_serial.to_string(),
+            is_emulator,
+            emulator_name: emulator_name.to_string(),
+        }
+    }
+
+    pub fn devices(&self) -> Result<Vec<SdbDevice>, TizenError> {
+        let mut stream = self.connect()?;
+        send_request(&mut stream, "host:devices")?;
+        let list = read_length_prefixed(&mut stream)?;
+
+        Ok(list
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let mut pieces = line.split_whitespace();
+                SdbDevice {
+                    serial: pieces.next().unwrap_or("").to_string(),
+                    state: pieces.next().unwrap_or("").to_string(),
+                    name: pieces.collect::<Vec<&str>>().join(" "),
+                }
+            })
+            .collect())
+    }
+
+    // Uses the configured serial, otherwise the only target connected, or the only
+    // emulator (or device) when both are connected, or the configured emulator name
+    pub fn select_device(&self) -> Result<SdbDevice, TizenError> {
+        let devices = self.devices()?;
+        let serials = devices
+            .iter()
+            .map(|device| format!("{} ({})", &device.serial, &device.state))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        if !self.target_serial.is_empty() {
+            return match devices
+                .into_iter()
+                .find(|device| device.serial == self.target_serial)
+            {
+                Some(device) if device.state == "device" => Ok(device),
+                Some(device) => Err(TizenError {
+                    message: format!("Target {} is {}", &device.serial, &device.state),
+                }),
+                None => Err(TizenError {
+                    message: format!(
+                        "Target {} not connected, connected targets: {}",
+                        &self.target_serial, serials
+                    ),
+                }),
+            };
+        }
+
+        let mut online: Vec<SdbDevice> = devices
+            .into_iter()
+            .filter(|device| device.state == "device")
+            .collect();
+
+        if let (true, Some(is_emulator)) = (online.len() > 1, self.is_emulator) {
+            online.retain(|device| device.is_emulator() == is_emulator);
+        }
+
+        if online.len() > 1 && self.is_emulator == Some(true) && !self.emulator_name.is_empty() {
+            online.retain(|device| device.name == self.emulator_name);
+        }
+
+        match online.len() {
+            0 => Err(TizenError {
+                message: "No device or emulator connected to sdb".to_string(),
+            }),
+            1 => Ok(online.remove(0)),
+            _ => Err(TizenError {
+                message: format!(
+                    "More than one target connected, select one with --serial or tizen.target_serial: {}",
+                    serials
+                ),
+            }),
+        }
+    }
+
+    // Reads cpu_arch from the sdbd capabilities, older targets fall back to uname
+    pub fn arch(&self, serial: &str) -> Result<String, TizenError> {
+        if let Ok(capabilities) = self.capabilities(serial) {
+            if let Some(arch) = capabilities
+                .iter()
+                .find(|(key, _)| key == "cpu_arch")
+                .map(|(_, value)| value.clone())
+            {
+                return Ok(arch);
+            }
+        }
+
+        let mut arch = String::new();
+        self.shell(serial, "uname -m", &mut |line| arch.push_str(line.trim()))?;
+
+        Ok(arch)
+    }
+
+    pub fn capabilities(&self, serial: &str) -> Result<Vec<(String, String)>, TizenError> {
+        let mut stream = self.open_transport(serial)?;
+        send_request(&mut stream, "capability:")?;
+
+        let mut content = String::new();
+        stream.read_to_string(&mut content)?;
+
+        Ok(content
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect())
+    }
+
+    // Runs a shell command and returns its exit code, sdb does not report it so
+    // the command echoes a marker with $? at the end
+    pub fn shell(
+        &self,
+        serial: &str,
+        command: &str,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<i32, TizenError> {
+        let stream = self.open_shell(serial, &format!("{}; echo {}$?", command, EXIT_MARKER))?;
+        // The command can run silently for any time (installs, debug sessions, tests)
+        stream.set_read_timeout(None)?;
+        let mut exit_code: Option<i32> = None;
+
+        for line in BufReader::new(stream).lines() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+
+            match line.find(EXIT_MARKER) {
+                Some(pos) => {
+                    if pos > 0 {
+                        on_line(&line[..pos]);
+                    }
+                    exit_code = line[pos + EXIT_MARKER.len()..].trim().parse::<i32>().ok();
+                }
+                None => on_line(line),
+            }
+        }
+
+        match exit_code {
+            Some(exit_code) => Ok(exit_code),
+            None => Err(TizenError {
+                message: format!("Lost connection while running {}", command),
+            }),
+        }
+    }
+
+    // Pushes a local binary to the temporary folder, runs it from there and removes it
+    pub fn exec(
+        &self,
+        serial: &str,
+        local_path: &Path,
+        args: &[String],
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<i32, TizenError> {
+        let file_name = match local_path.file_name().and_then(|name| name.to_str()) {
+            Some(file_name) => file_name,
+            None => {
+                return Err(TizenError {
+                    message: format!("Invalid binary path {}", local_path.to_str().unwrap_or("")),
+                })
+            }
+        };
+        let remote_path = format!("{}/{}", REMOTE_TMP_DIR, file_name);
+
+        self.shell(serial, &format!("mkdir -p {}", REMOTE_TMP_DIR), &mut |_| {})?;
+        self.push(serial, local_path, &remote_path, 0o755)?;
+
+        let command = [
+            vec![
+                format!("cd {} &&", REMOTE_TMP_DIR),
+                shell_quote(&remote_path),
+            ],
+            args.iter().map(|arg| shell_quote(arg)).collect(),
+        ]
+        .concat()
+        .join(" ");
+
+        let exit_code = self.shell(serial, &command, on_line)?;
+
+        self.shell(
+            serial,
+            &format!("rm -f {}", shell_quote(&remote_path)),
+            &mut |_| {},
+        )?;
+
+        Ok(exit_code)
+    }
+
+    // Same as sdb root on, emulators accept it while devices usually refuse it
+    pub fn root_on(&self, serial: &str) -> Result<(), TizenError> {
+        let mut stream = self.open_transport(serial)?;
+        send_request(&mut stream, "root:on")?;
+
+        let mut content = String::new();
+        stream.read_to_string(&mut content)?;
+
+        Ok(())
+    }
+
+    pub fn open_shell(&self, serial: &str, command: &str) -> Result<TcpStream, TizenError> {
+        let mut stream = self.open_transport(serial)?;
+        send_request(&mut stream, &format!("shell:{}", command))?;
+
+        Ok(stream)
+    }
+
+    pub fn push(
+        &self,
+        serial: &str,
+        local_path: &Path,
+        remote_path: &str,
+        mode: u32,
+    ) -> Result<(), TizenError> {
+        let mut file = File::open(local_path)?;
+        let mtime = file
+            .metadata()?
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+            })
+            .as_secs() as u32;
+
+        let mut stream = self.open_transport(serial)?;
+        send_request(&mut stream, "sync:")?;
+
+        let target = format!("{},{}", remote_path, mode);
+        send_sync(&mut stream, b"SEND", target.len() as u32, target.as_bytes())?;
+
+        let mut buffer = vec![0u8; SYNC_CHUNK_SIZE];
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            send_sync(&mut stream, b"DATA", read as u32, &buffer[..read])?;
+        }
+
+        send_sync(&mut stream, b"DONE", mtime, &[])?;
+
+        let mut header = [0u8; 8];
+        stream.read_exact(&mut header)?;
+        let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+
+        if &header[..4] != b"OKAY" {
+            let mut message = vec![0u8; length];
+            stream.read_exact(&mut message)?;
+            return Err(TizenError {
+                message: format!(
+                    "Failed to push {}: {}",
+                    remote_path,
+                    String::from_utf8_lossy(&message)
+                ),
+            });
+        }
+
+        send_sync(&mut stream, b"QUIT", 0, &[])?;
+
+        Ok(())
+    }
+
+    // The server answers twice, once for the transport and once for the forward
+    pub fn forward(
+        &self,
+        serial: &str,
+        local_port: u16,
+        remote_port: u16,
+    ) -> Result<(), TizenError> {
+        let mut stream = self.connect()?;
+        let request = format!(
+            "host-serial:{}:forward:tcp:{};tcp:{}",
+            serial, local_port, remote_port
+        );
+
+        send_request(&mut stream, &request)?;
+        read_status(&mut stream, &request)
+    }
+
+    fn open_transport(&self, serial: &str) -> Result<TcpStream, TizenError> {
+        let mut stream = self.connect()?;
+        send_request(&mut stream, &format!("host:transport:{}", serial))?;
+
+        Ok(stream)
+    }
+
+    // A stuck server or target fails the request instead of blocking forever
+    fn connect(&self) -> Result<TcpStream, TizenError> {
+        let stream = self.connect_server()?;
+        stream.set_read_timeout(Some(SOCKET_TIMEOUT))?;
+        stream.set_write_timeout(Some(SOCKET_TIMEOUT))?;
+
+        Ok(stream)
+    }
+
+    fn connect_server(&self) -> Result<TcpStream, TizenError> {
+        if let Ok(stream) = TcpStream::connect(("127.0.0.1", self.port)) {
+            return Ok(stream);
+        }
+
+        println!(
+            "Running: {} {}",
+            &self.sdb_bin.green().bold(),
+            "start-server".green().bold()
+        );
+
+        let started = Command::new(&self.sdb_bin)
+            .arg("start-server")
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false);
+
+        if started {
+            for _ in 0..10 {
+                if let Ok(stream) = TcpStream::connect(("127.0.0.1", self.port)) {
+                    return Ok(stream);
+                }
+                thread::sleep(Duration::from_millis(200));
+            }
+        }
+
+        Err(TizenError {
+            message: format!(
+                "Can't connect to the sdb server on port {}, start it with {} start-server",
+                self.port, &self.sdb_bin
+            ),
+        })
+    }
+}
+
+fn send_request(stream: &mut TcpStream, request: &str) -> Result<(), TizenError> {
+    stream.write_all(format!("{:04x}{}", request.len(), request).as_bytes())?;
+
+    read_status(stream, request)
+}
+
+fn read_status(stream: &mut TcpStream, request: &str) -> Result<(), TizenError> {
+    let mut status = [0u8; 4];
+    stream.read_exact(&mut status)?;
+
+    match &status {
+        b"OKAY" => Ok(()),
+        b"FAIL" => Err(TizenError {
+            message: format!(
+                "sdb {} failed: {}",
+                request,
+                read_length_prefixed(stream).unwrap_or_default()
+            ),
+        }),
+        _ => Err(TizenError {
+            message: format!("Unexpected sdb response to {}", request),
+        }),
+    }
+}
+
+fn read_length_prefixed(stream: &mut TcpStream) -> Result<String, TizenError> {
+    let mut length_hex = [0u8; 4];
+    stream.read_exact(&mut length_hex)?;
+
+    let length = match usize::from_str_radix(&String::from_utf8_lossy(&length_hex), 16) {
+        Ok(length) => length,
+        Err(_) => {
+            return Err(TizenError {
+                message: "Invalid sdb response length".to_string(),
+            })
+        }
+    };
+
+    let mut content = vec![0u8; length];
+    stream.read_exact(&mut content)?;
+
+    Ok(String::from_utf8_lossy(&content).to_string())
+}
+
+fn send_sync(
+    stream: &mut TcpStream,
+    id: &[u8],
+    length: u32,
+    data: &[u8],
+) -> Result<(), TizenError> {
+    let mut message = Vec::with_capacity(8 + data.len());
+    message.extend_from_slice(id);
+    message.extend_from_slice(&length.to_le_bytes());
+    message.extend_from_slice(data);
+
+    stream.write_all(&message)?;
+
+    Ok(())
+}
+
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+pub fn is_emulator_arch(arch: &str) -> bool {
+    matches!(
+        arch_triple(arch),
+        Some("i586-linux-gnueabi") | Some("x86_64-linux-gnu")
+    )
+}
+
+pub fn arch_triple(arch: &str) -> Option<&str> {
+    match arch {
+        "aarch64" | "arm64" => Some("aarch64-linux-gnu"),
+        "x86_64" => Some("x86_64-linux-gnu"),
+        "i386" | "i486" | "i586" | "i686" | "x86" => Some("i586-linux-gnueabi"),
+        _ if arch.starts_with("arm") => Some("arm-linux-gnueabi"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    // Serves the scripted connections of one test, returning what the stub received
+    fn serve<T: Send + 'static>(
+        handler: impl FnOnce(TcpListener) -> T + Send + 'static,
+    ) -> (SdbClient, JoinHandle<T>) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let client = SdbClient::with_config(port, "false", "", None, "");
+
+        (client, thread::spawn(move || handler(listener)))
+    }
+
+    fn read_request(stream: &mut TcpStream) -> String {
+        let mut length_hex = [0u8; 4];
+        stream.read_exact(&mut length_hex).unwrap();
+        let length = usize::from_str_radix(std::str::from_utf8(&length_hex).unwrap(), 16).unwrap();
+
+        let mut request = vec![0u8; length];
+        stream.read_exact(&mut request).unwrap();
+
+        String::from_utf8(request).unwrap()
+    }
+
+    fn accept_transport(listener: &TcpListener, serial: &str) -> TcpStream {
+        let (mut stream, _) = listener.accept().unwrap();
+        assert_eq!(
+            read_request(&mut stream),
+            format!("host:transport:{}", serial)
+        );
+        stream.write_all(b"OKAY").unwrap();
+
+        stream
+    }
+
+    fn read_sync(stream: &mut TcpStream) -> (String, u32, Vec<u8>) {
+        let mut header = [0u8; 8];
+        stream.read_exact(&mut header).unwrap();
+        let id = String::from_utf8_lossy(&header[..4]).to_string();
+        let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+
+        let mut data = vec![];
+        if id == "SEND" || id == "DATA" {
+            data.resize(length as usize, 0);
+            stream.read_exact(&mut data).unwrap();
+        }
+
+        (id, length, data)
+    }
+
+    #[test]
+    fn devices_are_parsed_from_the_host_list() {
+        let (sdb, server) = serve(|listener| {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = read_request(&mut stream);
+
+            let list = "emulator-26101\tdevice\tw-5.5\nR3AN1234\toffline\tGalaxy Watch 4\n";
+            stream
+                .write_all(format!("OKAY{:04x}{}", list.len(), list).as_bytes())
+                .unwrap();
+
+            request
+        });
+
+        let devices = sdb.devices().unwrap();
+
+        assert_eq!(server.join().unwrap(), "host:devices");
+        assert_eq!(devices.len(), 2);
+        assert!(devices[0].is_emulator());
+        assert_eq!(devices[0].state, "device");
+        assert_eq!(devices[1].serial, "R3AN1234");
+        assert_eq!(devices[1].state, "offline");
+        assert_eq!(devices[1].name, "Galaxy Watch 4");
+    }
+
+    #[test]
+    fn shell_reads_the_exit_code_marker() {
+        let (sdb, server) = serve(|listener| {
+            let mut stream = accept_transport(&listener, "emulator-26101");
+            let request = read_request(&mut stream);
+
+            stream.write_all(b"OKAY").unwrap();
+            stream
+                .write_all(format!("first\r\nlast{}3\r\n", EXIT_MARKER).as_bytes())
+                .unwrap();
+
+            request
+        });
+
+        let mut lines: Vec<String> = vec![];
+        let exit_code = sdb
+            .shell("emulator-26101", "ls 'a b'", &mut |line| {
+                lines.push(line.to_string())
+            })
+            .unwrap();
+
+        assert_eq!(
+            server.join().unwrap(),
+            format!("shell:ls 'a b'; echo {}$?", EXIT_MARKER)
+        );
+        assert_eq!(exit_code, 3);
+        assert_eq!(lines, vec!["first", "last"]);
+    }
+
+    #[test]
+    fn shell_fails_without_the_exit_code_marker() {
+        let (sdb, server) = serve(|listener| {
+            let mut stream = accept_transport(&listener, "emulator-26101");
+            read_request(&mut stream);
+            stream.write_all(b"OKAY partial output").unwrap();
+        });
+
+        let result = sdb.shell("emulator-26101", "reboot", &mut |_| {});
+        server.join().unwrap();
+
+        assert_eq!(
+            result.unwrap_err().message,
+            "Lost connection while running reboot"
+        );
+    }
+
+    #[test]
+    fn push_sends_the_file_in_sync_messages() {
+        let local = tempfile::NamedTempFile::new().unwrap();
+        let content: Vec<u8> = (0..SYNC_CHUNK_SIZE + 10).map(|i| i as u8).collect();
+        fs::write(local.path(), &content).unwrap();
+
+        let (sdb, server) = serve(|listener| {
+            let mut stream = accept_transport(&listener, "R3AN1234");
+            assert_eq!(read_request(&mut stream), "sync:");
+            stream.write_all(b"OKAY").unwrap();
+
+            let mut messages = vec![];
+            loop {
+                let message = read_sync(&mut stream);
+                let is_done = message.0 == "DONE";
+                messages.push(message);
+
+                if is_done {
+                    break;
+                }
+            }
+
+            stream.write_all(b"OKAY\0\0\0\0").unwrap();
+            messages.push(read_sync(&mut stream));
+
+            messages
+        });
+
+        sdb.push("R3AN1234", local.path(), "/tmp/app.tpk", 0o644)
+            .unwrap();
+        let messages = server.join().unwrap();
+
+        let ids: Vec<&str> = messages.iter().map(|(id, _, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["SEND", "DATA", "DATA", "DONE", "QUIT"]);
+        assert_eq!(messages[0].2, b"/tmp/app.tpk,420");
+        assert_eq!(messages[1].1 as usize, SYNC_CHUNK_SIZE);
+        assert_eq!(
+            [messages[1].2.clone(), messages[2].2.clone()].concat(),
+            content
+        );
+
+        let mtime = fs::metadata(local.path())
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert_eq!(messages[3].1 as u64, mtime);
+    }
+
+    #[test]
+    fn push_reports_the_sync_failure() {
+        let local = tempfile::NamedTempFile::new().unwrap();
+
+        let (sdb, server) = serve(|listener| {
+            let mut stream = accept_transport(&listener, "R3AN1234");
+            read_request(&mut stream);
+            stream.write_all(b"OKAY").unwrap();
+
+            while read_sync(&mut stream).0 != "DONE" {}
+
+            let message = "No space left on device";
+            stream.write_all(b"FAIL").unwrap();
+            stream
+                .write_all(&(message.len() as u32).to_le_bytes())
+                .unwrap();
+            stream.write_all(message.as_bytes()).unwrap();
+        });
+
+        let result = sdb.push("R3AN1234", local.path(), "/tmp/app.tpk", 0o644);
+        server.join().unwrap();
+
+        assert_eq!(
+            result.unwrap_err().message,
+            "Failed to push /tmp/app.tpk: No space left on device"
+        );
+    }
+}
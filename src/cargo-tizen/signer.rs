@@ -0,0 +1,800 @@
+use crate::error::TizenError;
+use crate::tpk::{entry_name, list_entries};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rsa::pkcs1v15::{Signature, SigningKey, VerifyingKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::signature::{SignatureEncoding, Signer, Verifier};
+use rsa::traits::PublicKeyParts;
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use sha2::{Digest, Sha512};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use sxd_document::dom::{ChildOfElement, Element};
+use sxd_document::parser;
+use sxd_xpath::nodeset::Node;
+use sxd_xpath::{Context, Factory, Value};
+
+const XMLDSIG_NS: &str = "http://www.w3.org/2000/09/xmldsig#";
+const C14N11: &str = "http://www.w3.org/2006/12/xml-c14n11";
+const RSA_SHA512: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha512";
+const SHA512: &str = "http://www.w3.org/2001/04/xmlenc#sha512";
+
+pub const AUTHOR_SIGNATURE: &str = "author-signature.xml";
+pub const DISTRIBUTOR_SIGNATURE: &str = "signature1.xml";
+
+pub enum SignatureRole {
+    Author,
+    Distributor,
+}
+
+impl SignatureRole {
+    fn id(&self) -> &str {
+        match self {
+            SignatureRole::Author => "AuthorSignature",
+            SignatureRole::Distributor => "DistributorSignature",
+        }
+    }
+
+    fn role_uri(&self) -> &str {
+        match self {
+            SignatureRole::Author => "http://www.w3.org/ns/widgets-digsig#role-author",
+            SignatureRole::Distributor => "http://www.w3.org/ns/widgets-digsig#role-distributor",
+        }
+    }
+
+    fn file_name(&self) -> &str {
+        match self {
+            SignatureRole::Author => AUTHOR_SIGNATURE,
+            SignatureRole::Distributor => DISTRIBUTOR_SIGNATURE,
+        }
+    }
+}
+
+pub struct SigningCert {
+    key: RsaPrivateKey,
+    certs: Vec<Vec<u8>>,
+}
+
+impl SigningCert {
+    pub fn from_p12(path: &Path, password: &str) -> Result<Self, TizenError> {
+        let path_str = path.to_str().unwrap_or("");
+        let pfx = p12::PFX::parse(&fs::read(path)?).map_err(|_| TizenError {
+            message: format!("Can't parse certificate {}", path_str),
+        })?;
+
+        if !pfx.verify_mac(password) {
+            return Err(TizenError {
+                message: format!("Invalid password for certificate {}", path_str),
+            });
+        }
+
+        let key_der = match pfx.key_bags(password) {
+            Ok(key_bags) if !key_bags.is_empty() => key_bags[0].clone(),
+            _ => {
+                return Err(TizenError {
+                    message: format!("No private key found in {}", path_str),
+                })
+            }
+        };
+
+        let key = RsaPrivateKey::from_pkcs8_der(&key_der).map_err(|_| TizenError {
+            message: format!("Unsupported private key in {}", path_str),
+        })?;
+
+        let mut certs = pfx.cert_x509_bags(password).map_err(|_| TizenError {
+            message: format!("Can't read certificates from {}", path_str),
+        })?;
+
+        // The signer certificate must come first in KeyInfo, find it by its modulus
+        let modulus = key.n().to_bytes_be();
+        match certs
+            .iter()
+            .position(|cert| cert.windows(modulus.len()).any(|w| w == &modulus[..]))
+        {
+            Some(position) => {
+                let signer_cert = certs.remove(position);
+                certs.insert(0, signer_cert);
+            }
+            None => {
+                return Err(TizenError {
+                    message: format!("No certificate matches the private key in {}", path_str),
+                })
+            }
+        }
+
+        Ok(Self { key, certs })
+    }
+}
+
+pub fn sign_package(
+    staging_dir: &Path,
+    excludes: &[PathBuf],
+    author: &SigningCert,
+    distributor: &SigningCert,
+) -> Result<(), TizenError> {
+    for signature_file in [AUTHOR_SIGNATURE, DISTRIBUTOR_SIGNATURE].iter() {
+        let mut signature_path = PathBuf::from(staging_dir);
+        signature_path.push(signature_file);
+
+        if signature_path.exists() {
+            fs::remove_file(&signature_path)?;
+        }
+    }
+
+    write_signature(staging_dir, excludes, &SignatureRole::Author, author)?;
+    write_signature(
+        staging_dir,
+        excludes,
+        &SignatureRole::Distributor,
+        distributor,
+    )?;
+
+    Ok(())
+}
+
+fn write_signature(
+    staging_dir: &Path,
+    excludes: &[PathBuf],
+    role: &SignatureRole,
+    signing_cert: &SigningCert,
+) -> Result<(), TizenError> {
+    let mut signed_info = String::new();
+    signed_info.push_str(&format!(
+        "\n<CanonicalizationMethod Algorithm=\"{}\"></CanonicalizationMethod>",
+        C14N11
+    ));
+    signed_info.push_str(&format!(
+        "\n<SignatureMethod Algorithm=\"{}\"></SignatureMethod>",
+        RSA_SHA512
+    ));
+
+    for file_path in signed_files(staging_dir, excludes)? {
+        let uri = encode_uri(&entry_name(staging_dir, &file_path));
+        signed_info.push_str(&make_reference(&uri, &digest_file(&file_path)?, false));
+    }
+
+    let object = make_object(role);
+    let canonical_object =
+        object.replacen("<Object", &format!("<Object xmlns=\"{}\"", XMLDSIG_NS), 1);
+    signed_info.push_str(&make_reference(
+        "#prop",
+        &BASE64.encode(Sha512::digest(canonical_object.as_bytes())),
+        true,
+    ));
+    signed_info.push('\n');
+
+    let canonical_signed_info = format!(
+        "<SignedInfo xmlns=\"{}\">{}</SignedInfo>",
+        XMLDSIG_NS, &signed_info
+    );
+    let signature_value = SigningKey::<Sha512>::new(signing_cert.key.clone())
+        .sign(canonical_signed_info.as_bytes())
+        .to_bytes();
+
+    let mut signature_path = PathBuf::from(staging_dir);
+    signature_path.push(role.file_name());
+
+    let mut file = File::create(&signature_path)?;
+
+    writeln!(
+        file,
+        "<Signature xmlns=\"{}\" Id=\"{}\">",
+        XMLDSIG_NS,
+        role.id()
+    )?;
+    writeln!(file, "<SignedInfo>{}</SignedInfo>", &signed_info)?;
+    writeln!(
+        file,
+        "<SignatureValue>\n{}\n</SignatureValue>",
+        BASE64.encode(&signature_value)
+    )?;
+    writeln!(file, "<KeyInfo>\n<X509Data>")?;
+    for cert in signing_cert.certs.iter() {
+        writeln!(
+            file,
+            "<X509Certificate>\n{}\n</X509Certificate>",
+            BASE64.encode(cert)
+        )?;
+    }
+    writeln!(file, "</X509Data>\n</KeyInfo>")?;
+    writeln!(file, "{}", &object)?;
+    writeln!(file, "</Signature>")?;
+
+    verify_signature(staging_dir, &signature_path)
+}
+
+fn signed_files(staging_dir: &Path, excludes: &[PathBuf]) -> Result<Vec<PathBuf>, TizenError> {
+    Ok(list_entries(staging_dir, excludes)?
+        .into_iter()
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let name = entry_name(staging_dir, path);
+            !(name.starts_with("signature") && name.ends_with(".xml"))
+        })
+        .collect())
+}
+
+fn make_reference(uri: &str, digest: &str, with_transform: bool) -> String {
+    let mut reference = format!("\n<Reference URI=\"{}\">", uri);
+
+    if with_transform {
+        reference.push_str(&format!(
+            "\n<Transforms>\n<Transform Algorithm=\"{}\"></Transform>\n</Transforms>",
+            C14N11
+        ));
+    }
+
+    reference.push_str(&format!(
+        "\n<DigestMethod Algorithm=\"{}\"></DigestMethod>\n<DigestValue>{}</DigestValue>\n</Reference>",
+        SHA512, digest
+    ));
+
+    reference
+}
+
+fn make_object(role: &SignatureRole) -> String {
+    format!(
+        concat!(
+            "<Object Id=\"prop\">",
+            "<SignatureProperties xmlns:dsp=\"http://www.w3.org/2009/xmldsig-properties\">",
+            "<SignatureProperty Id=\"profile\" Target=\"#{id}\">",
+            "<dsp:Profile URI=\"http://www.w3.org/ns/widgets-digsig#profile\"></dsp:Profile>",
+            "</SignatureProperty>",
+            "<SignatureProperty Id=\"role\" Target=\"#{id}\">",
+            "<dsp:Role URI=\"{role}\"></dsp:Role>",
+            "</SignatureProperty>",
+            "<SignatureProperty Id=\"identifier\" Target=\"#{id}\">",
+            "<dsp:Identifier></dsp:Identifier>",
+            "</SignatureProperty>",
+            "</SignatureProperties>",
+            "</Object>"
+        ),
+        id = role.id(),
+        role = role.role_uri()
+    )
+}
+
+fn digest_file(path: &Path) -> Result<String, TizenError> {
+    let mut hasher = Sha512::new();
+    std::io::copy(&mut File::open(path)?, &mut hasher)?;
+
+    Ok(BASE64.encode(hasher.finalize()))
+}
+
+fn encode_uri(name: &str) -> String {
+    name.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn decode_uri(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded: Vec<u8> = vec![];
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        if bytes[pos] == b'%' && pos + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[pos + 1..pos + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                pos += 3;
+                continue;
+            }
+        }
+
+        decoded.push(bytes[pos]);
+        pos += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+// Checks the file and #prop digests, then the RSA signature of the canonical
+// SignedInfo with the first X509Certificate of KeyInfo
+pub fn verify_signature(staging_dir: &Path, signature_path: &Path) -> Result<(), TizenError> {
+    let package = parser::parse(&fs::read_to_string(signature_path)?)?;
+    let document = package.as_document();
+    let signature_name = signature_path.to_str().unwrap_or("");
+
+    let invalid = |what: &str| TizenError {
+        message: format!("Invalid {} in {}", what, signature_name),
+    };
+
+    let signed_info = find_elements(document.root(), "/ds:Signature/ds:SignedInfo")
+        .into_iter()
+        .next()
+        .ok_or_else(|| invalid("SignedInfo"))?;
+
+    for reference in find_elements(signed_info, "ds:Reference") {
+        let uri = reference.attribute_value("URI").unwrap_or("");
+
+        let expected = find_elements(reference, "ds:DigestValue")
+            .first()
+            .map(|digest| element_text(*digest))
+            .unwrap_or_default();
+
+        let digest = match uri.strip_prefix('#') {
+            Some(id) => find_elements(
+                document.root(),
+                &format!("/ds:Signature/ds:Object[@Id='{}']", id),
+            )
+            .first()
+            .map(|object| BASE64.encode(Sha512::digest(canonicalize(*object).as_bytes()))),
+            None => {
+                let mut file_path = PathBuf::from(staging_dir);
+                file_path.push(decode_uri(uri));
+
+                match file_path.is_file() {
+                    true => Some(digest_file(&file_path)?),
+                    false => None,
+                }
+            }
+        };
+
+        if digest.as_deref() != Some(expected.trim()) {
+            return Err(invalid(&format!("digest for {}", uri)));
+        }
+    }
+
+    let signature_value = find_elements(document.root(), "/ds:Signature/ds:SignatureValue")
+        .first()
+        .and_then(|value| decode_base64_text(*value))
+        .ok_or_else(|| invalid("SignatureValue"))?;
+
+    let public_key = find_elements(
+        document.root(),
+        "/ds:Signature/ds:KeyInfo/ds:X509Data/ds:X509Certificate",
+    )
+    .first()
+    .and_then(|cert| decode_base64_text(*cert))
+    .and_then(|cert| {
+        subject_public_key_info(&cert).and_then(|spki| RsaPublicKey::from_public_key_der(spki).ok())
+    })
+    .ok_or_else(|| invalid("X509Certificate"))?;
+
+    let signature =
+        Signature::try_from(&signature_value[..]).map_err(|_| invalid("SignatureValue"))?;
+
+    VerifyingKey::<Sha512>::new(public_key)
+        .verify(canonicalize(signed_info).as_bytes(), &signature)
+        .map_err(|_| invalid("SignatureValue"))
+}
+
+fn find_elements<'d, N>(node: N, path: &str) -> Vec<Element<'d>>
+where
+    N: Into<Node<'d>>,
+{
+    let mut context = Context::new();
+    context.set_namespace("ds", XMLDSIG_NS);
+
+    let expression = match Factory::new().build(path) {
+        Ok(Some(expression)) => expression,
+        _ => return vec![],
+    };
+
+    match expression.evaluate(&context, node) {
+        Ok(Value::Nodeset(nodes)) => nodes
+            .document_order()
+            .into_iter()
+            .filter_map(|node| node.element())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn element_text(element: Element) -> String {
+    element
+        .children()
+        .into_iter()
+        .filter_map(|child| child.text())
+        .map(|text| text.text().to_string())
+        .collect()
+}
+
+fn decode_base64_text(element: Element) -> Option<Vec<u8>> {
+    let text: String = element_text(element)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    BASE64.decode(text).ok()
+}
+
+// Canonical XML 1.1 of an element without comments, as it was digested or signed:
+// namespaces are declared where they are first rendered and attributes are sorted
+fn canonicalize(element: Element) -> String {
+    let mut output = String::new();
+    write_canonical(element, &BTreeMap::new(), &mut output);
+    output
+}
+
+fn write_canonical(element: Element, rendered: &BTreeMap<String, String>, output: &mut String) {
+    let mut in_scope: BTreeMap<String, String> = BTreeMap::new();
+
+    if let Some(uri) = element.recursive_default_namespace_uri() {
+        in_scope.insert(String::new(), uri.to_string());
+    }
+
+    for namespace in element.namespaces_in_scope() {
+        if namespace.prefix() != "xml" {
+            in_scope
+                .entry(namespace.prefix().to_string())
+                .or_insert_with(|| namespace.uri().to_string());
+        }
+    }
+
+    let qualified_name = match element.preferred_prefix() {
+        Some(prefix) => format!("{}:{}", prefix, element.name().local_part()),
+        None => element.name().local_part().to_string(),
+    };
+
+    output.push('<');
+    output.push_str(&qualified_name);
+
+    for (prefix, uri) in in_scope.iter() {
+        if rendered.get(prefix) == Some(uri) {
+            continue;
+        }
+
+        match prefix.is_empty() {
+            true => output.push_str(&format!(" xmlns=\"{}\"", escape_attribute(uri))),
+            false => output.push_str(&format!(" xmlns:{}=\"{}\"", prefix, escape_attribute(uri))),
+        }
+    }
+
+    let mut attributes: Vec<(String, String, String)> = element
+        .attributes()
+        .into_iter()
+        .map(|attribute| {
+            let name = match attribute.preferred_prefix() {
+                Some(prefix) => format!("{}:{}", prefix, attribute.name().local_part()),
+                None => attribute.name().local_part().to_string(),
+            };
+            (
+                attribute.name().namespace_uri().unwrap_or("").to_string(),
+                name,
+                attribute.value().to_string(),
+            )
+        })
+        .collect();
+    attributes.sort();
+
+    for (_, name, value) in attributes.iter() {
+        output.push_str(&format!(" {}=\"{}\"", name, escape_attribute(value)));
+    }
+
+    output.push('>');
+
+    for child in element.children() {
+        match child {
+            ChildOfElement::Element(child) => write_canonical(child, &in_scope, output),
+            ChildOfElement::Text(text) => output.push_str(&escape_text(text.text())),
+            ChildOfElement::ProcessingInstruction(pi) => {
+                output.push_str(&format!("<?{}", pi.target()));
+                if let Some(value) = pi.value() {
+                    output.push_str(&format!(" {}", value));
+                }
+                output.push_str("?>");
+            }
+            ChildOfElement::Comment(_) => {}
+        }
+    }
+
+    output.push_str(&format!("</{}>", qualified_name));
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\r', "&#xD;")
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('"', "&quot;")
+        .replace('\t', "&#x9;")
+        .replace('\n', "&#xA;")
+        .replace('\r', "&#xD;")
+}
+
+// The DER of the subjectPublicKeyInfo, the 7th field of tbsCertificate (6th
+// without the optional version)
+fn subject_public_key_info(cert: &[u8]) -> Option<&[u8]> {
+    let (_, certificate, _) = der_element(cert, 0)?;
+    let (_, tbs_certificate, _) = der_element(certificate, 0)?;
+
+    let mut pos = 0;
+    let (tag, _, end) = der_element(tbs_certificate, pos)?;
+    if tag == 0xa0 {
+        pos = end;
+    }
+
+    // serialNumber, signature, issuer, validity, subject
+    for _ in 0..5 {
+        pos = der_element(tbs_certificate, pos)?.2;
+    }
+
+    let end = der_element(tbs_certificate, pos)?.2;
+    tbs_certificate.get(pos..end)
+}
+
+// Returns the tag, the content and the end offset of the DER element at pos
+fn der_element(data: &[u8], pos: usize) -> Option<(u8, &[u8], usize)> {
+    let tag = *data.get(pos)?;
+    let first_length = *data.get(pos + 1)? as usize;
+
+    let (length, content_start) = match first_length < 0x80 {
+        true => (first_length, pos + 2),
+        false => {
+            let length_size = first_length & 0x7f;
+            let length = data
+                .get(pos + 2..pos + 2 + length_size)?
+                .iter()
+                .fold(0usize, |length, byte| (length << 8) | *byte as usize);
+            (length, pos + 2 + length_size)
+        }
+    };
+
+    let end = content_start.checked_add(length)?;
+    Some((tag, data.get(content_start..end)?, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rsa::pkcs8::{EncodePrivateKey, EncodePublicKey};
+    use rsa::rand_core::{impls, CryptoRng, Error, RngCore};
+    use tempfile::TempDir;
+
+    // Deterministic xorshift, the tests only need a valid key
+    struct TestRng(u64);
+
+    impl RngCore for TestRng {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            impls::fill_bytes_via_next(self, dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for TestRng {}
+
+    fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut encoded = vec![tag];
+        let length = content.len();
+
+        if length < 0x80 {
+            encoded.push(length as u8);
+        } else {
+            let bytes: Vec<u8> = length
+                .to_be_bytes()
+                .iter()
+                .copied()
+                .skip_while(|byte| *byte == 0)
+                .collect();
+            encoded.push(0x80 | bytes.len() as u8);
+            encoded.extend(bytes);
+        }
+
+        encoded.extend_from_slice(content);
+        encoded
+    }
+
+    fn der_sequence(parts: &[Vec<u8>]) -> Vec<u8> {
+        der(0x30, &parts.concat())
+    }
+
+    fn self_signed_p12(dir: &Path, common_name: &str, password: &str, seed: u64) -> PathBuf {
+        let key = RsaPrivateKey::new(&mut TestRng(seed), 1024).unwrap();
+        let spki = key.to_public_key().to_public_key_der().unwrap();
+
+        let sha512_with_rsa = der_sequence(&[
+            der(
+                0x06,
+                &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d],
+            ),
+            der(0x05, &[]),
+        ]);
+        let name = der_sequence(&[der(
+            0x31,
+            &der_sequence(&[
+                der(0x06, &[0x55, 0x04, 0x03]),
+                der(0x0c, common_name.as_bytes()),
+            ]),
+        )]);
+        let validity = der_sequence(&[der(0x17, b"240101000000Z"), der(0x17, b"490101000000Z")]);
+
+        let tbs_certificate = der_sequence(&[
+            der(0xa0, &der(0x02, &[2])),
+            der(0x02, &[1]),
+            sha512_with_rsa.clone(),
+            name.clone(),
+            validity,
+            name,
+            spki.as_bytes().to_vec(),
+        ]);
+
+        let mut signature_bits = vec![0];
+        signature_bits.extend(
+            SigningKey::<Sha512>::new(key.clone())
+                .sign(&tbs_certificate)
+                .to_bytes()
+                .iter(),
+        );
+
+        let cert = der_sequence(&[tbs_certificate, sha512_with_rsa, der(0x03, &signature_bits)]);
+        let key_der = key.to_pkcs8_der().unwrap();
+
+        let pfx = p12::PFX::new(&cert, key_der.as_bytes(), None, password, common_name).unwrap();
+
+        let p12_path = dir.join(format!("{}.p12", common_name));
+        fs::write(&p12_path, pfx.to_der()).unwrap();
+        p12_path
+    }
+
+    fn signed_staging() -> (TempDir, TempDir) {
+        let certs_dir = TempDir::new().unwrap();
+        let staging_dir = TempDir::new().unwrap();
+
+        let author = SigningCert::from_p12(
+            &self_signed_p12(
+                certs_dir.path(),
+                "author",
+                "author-pass",
+                0x2545_f491_4f6c_dd1d,
+            ),
+            "author-pass",
+        )
+        .unwrap();
+        let distributor = SigningCert::from_p12(
+            &self_signed_p12(
+                certs_dir.path(),
+                "distributor",
+                "dist-pass",
+                0x9e37_79b9_7f4a_7c15,
+            ),
+            "dist-pass",
+        )
+        .unwrap();
+
+        fs::create_dir_all(staging_dir.path().join("bin")).unwrap();
+        fs::create_dir_all(staging_dir.path().join("res")).unwrap();
+        fs::write(staging_dir.path().join("bin/app"), b"\x7fELF binary").unwrap();
+        fs::write(staging_dir.path().join("res/a b.txt"), b"resource").unwrap();
+        fs::write(
+            staging_dir.path().join("tizen-manifest.xml"),
+            b"<manifest/>",
+        )
+        .unwrap();
+
+        sign_package(staging_dir.path(), &[], &author, &distributor).unwrap();
+
+        (certs_dir, staging_dir)
+    }
+
+    fn reference_digest(signed_info: Element, uri: &str) -> String {
+        find_elements(signed_info, "ds:Reference")
+            .into_iter()
+            .find(|reference| reference.attribute_value("URI") == Some(uri))
+            .map(|reference| element_text(find_elements(reference, "ds:DigestValue")[0]))
+            .unwrap()
+    }
+
+    #[test]
+    fn signatures_are_verifiable_after_parsing() {
+        let (_certs_dir, staging_dir) = signed_staging();
+
+        for (file_name, role) in [
+            (AUTHOR_SIGNATURE, SignatureRole::Author),
+            (DISTRIBUTOR_SIGNATURE, SignatureRole::Distributor),
+        ] {
+            let signature_path = staging_dir.path().join(file_name);
+            let package = parser::parse(&fs::read_to_string(&signature_path).unwrap()).unwrap();
+            let document = package.as_document();
+
+            let signed_info = find_elements(document.root(), "/ds:Signature/ds:SignedInfo")[0];
+
+            // The #prop digest is the one of the canonical Object, with the inherited namespace
+            let expected_object = make_object(&role).replacen(
+                "<Object",
+                &format!("<Object xmlns=\"{}\"", XMLDSIG_NS),
+                1,
+            );
+            let object = find_elements(document.root(), "/ds:Signature/ds:Object")[0];
+            assert_eq!(canonicalize(object), expected_object);
+            assert_eq!(
+                reference_digest(signed_info, "#prop"),
+                BASE64.encode(Sha512::digest(expected_object.as_bytes()))
+            );
+
+            assert_eq!(
+                reference_digest(signed_info, "res/a%20b.txt"),
+                digest_file(&staging_dir.path().join("res/a b.txt")).unwrap()
+            );
+
+            // The signature is checked with the public key of the embedded certificate
+            let cert = decode_base64_text(
+                find_elements(
+                    document.root(),
+                    "/ds:Signature/ds:KeyInfo/ds:X509Data/ds:X509Certificate",
+                )[0],
+            )
+            .unwrap();
+            let public_key =
+                RsaPublicKey::from_public_key_der(subject_public_key_info(&cert).unwrap()).unwrap();
+            let signature_value = decode_base64_text(
+                find_elements(document.root(), "/ds:Signature/ds:SignatureValue")[0],
+            )
+            .unwrap();
+
+            VerifyingKey::<Sha512>::new(public_key)
+                .verify(
+                    canonicalize(signed_info).as_bytes(),
+                    &Signature::try_from(&signature_value[..]).unwrap(),
+                )
+                .unwrap();
+
+            verify_signature(staging_dir.path(), &signature_path).unwrap();
+        }
+
+        // The distributor signs the author signature, not the other way around
+        let author = fs::read_to_string(staging_dir.path().join(AUTHOR_SIGNATURE)).unwrap();
+        let distributor =
+            fs::read_to_string(staging_dir.path().join(DISTRIBUTOR_SIGNATURE)).unwrap();
+        assert!(!author.contains("URI=\"signature1.xml\""));
+        assert!(distributor.contains("URI=\"author-signature.xml\""));
+    }
+
+    #[test]
+    fn tampered_packages_are_rejected() {
+        let (_certs_dir, staging_dir) = signed_staging();
+        let author_path = staging_dir.path().join(AUTHOR_SIGNATURE);
+        let author = fs::read_to_string(&author_path).unwrap();
+
+        fs::write(staging_dir.path().join("bin/app"), b"patched").unwrap();
+        assert!(verify_signature(staging_dir.path(), &author_path).is_err());
+        fs::write(staging_dir.path().join("bin/app"), b"\x7fELF binary").unwrap();
+        verify_signature(staging_dir.path(), &author_path).unwrap();
+
+        let tampered_object = author.replace("role-author", "role-distributor");
+        fs::write(&author_path, tampered_object).unwrap();
+        assert!(verify_signature(staging_dir.path(), &author_path).is_err());
+
+        // A digest changed in SignedInfo only breaks the RSA signature
+        let app_digest = digest_file(&staging_dir.path().join("bin/app")).unwrap();
+        fs::write(staging_dir.path().join("bin/app"), b"patched").unwrap();
+        let patched_digest = digest_file(&staging_dir.path().join("bin/app")).unwrap();
+        fs::write(&author_path, author.replace(&app_digest, &patched_digest)).unwrap();
+        assert!(verify_signature(staging_dir.path(), &author_path)
+            .unwrap_err()
+            .message
+            .contains("SignatureValue"));
+    }
+}
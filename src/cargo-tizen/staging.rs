@@ -0,0 +1,300 @@
+use crate::error::TizenError;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+pub enum StagedSource {
+    File(PathBuf),
+    Content(Vec<u8>),
+}
+
+pub struct SyncReport {
+    pub written: Vec<PathBuf>,
+    pub unchanged: usize,
+    pub removed: usize,
+}
+
+// Expected content of the staging folder, copied files keep the mtime of their
+// source so they are compared by mtime, generated ones are compared by content
+pub struct Staging {
+    root: PathBuf,
+    files: BTreeMap<PathBuf, StagedSource>,
+    dirs: BTreeSet<PathBuf>,
+    kept: Vec<PathBuf>,
+}
+
+impl Staging {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            files: BTreeMap::new(),
+            dirs: BTreeSet::new(),
+            kept: vec![],
+        }
+    }
+
+    // Leaves a path of the staging folder untouched
+    pub fn keep(&mut self, relative_path: &Path) {
+        self.kept.push(relative_path.to_path_buf());
+    }
+
+    pub fn add_file(&mut self, relative_path: &Path, source_path: &Path) {
+        self.files.insert(
+            relative_path.to_path_buf(),
+            StagedSource::File(source_path.to_path_buf()),
+        );
+    }
+
+    pub fn add_content(&mut self, relative_path: &Path, content: &[u8]) {
+        self.files.insert(
+            relative_path.to_path_buf(),
+            StagedSource::Content(content.to_vec()),
+        );
+    }
+
+    pub fn add_empty_dir(&mut self, relative_path: &Path) {
+        self.dirs.insert(relative_path.to_path_buf());
+    }
+
+    pub fn sync(&self) -> Result<SyncReport, TizenError> {
+        fs::create_dir_all(&self.root)?;
+
+        let mut report = SyncReport {
+            written: vec![],
+            unchanged: 0,
+            removed: self.remove_stale()?,
+        };
+
+        for relative_path in self.dirs.iter() {
+            fs::create_dir_all(self.root.join(relative_path))?;
+        }
+
+        for (relative_path, source) in self.files.iter() {
+            let dest_path = self.root.join(relative_path);
+
+            let is_unchanged = match source {
+                StagedSource::File(source_path) => same_mtime(source_path, &dest_path),
+                StagedSource::Content(content) => {
+                    fs::read(&dest_path).ok().as_deref() == Some(&content[..])
+                }
+            };
+
+            if is_unchanged {
+                report.unchanged += 1;
+                continue;
+            }
+
+            if let Some(parent) = dest_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            match source {
+                StagedSource::File(source_path) => {
+                    // A read-only copy from the previous sync can't be overwritten
+                    if dest_path.is_file() {
+                        fs::remove_file(&dest_path)?;
+                    }
+                    fs::copy(source_path, &dest_path)?;
+                    copy_mtime(source_path, &dest_path)?;
+                }
+                StagedSource::Content(content) => fs::write(&dest_path, content)?,
+            }
+
+            report.written.push(relative_path.clone());
+        }
+
+        Ok(report)
+    }
+
+    fn remove_stale(&self) -> Result<usize, TizenError> {
+        let dirs: BTreeSet<&Path> = self
+            .files
+            .keys()
+            .flat_map(|relative_path| relative_path.ancestors().skip(1))
+            .chain(
+                self.dirs
+                    .iter()
+                    .flat_map(|relative_path| relative_path.ancestors()),
+            )
+            .collect();
+
+        let mut removed = 0;
+        let mut stack: Vec<PathBuf> = vec![self.root.clone()];
+
+        while let Some(working_path) = stack.pop() {
+            for entry in fs::read_dir(&working_path)? {
+                let path = entry?.path();
+                let relative_path = path.strip_prefix(&self.root).unwrap_or(&path);
+
+                if self.kept.iter().any(|kept| relative_path.starts_with(kept)) {
+                    continue;
+                }
+
+                if path.is_dir() && !path.is_symlink() {
+                    if dirs.contains(relative_path) {
+                        stack.push(path.clone());
+                    } else {
+                        fs::remove_dir_all(&path)?;
+                        removed += 1;
+                    }
+                } else if !self.files.contains_key(relative_path) {
+                    fs::remove_file(&path)?;
+                    removed += 1;
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+// fs::copy keeps the permissions, a read-only copy is writable only while its mtime is set
+pub fn copy_mtime(source_path: &Path, dest_path: &Path) -> Result<(), TizenError> {
+    let modified = fs::metadata(source_path)?.modified()?;
+    let permissions = fs::metadata(dest_path)?.permissions();
+
+    if permissions.readonly() {
+        let mut writable = permissions.clone();
+        #[allow(clippy::permissions_set_readonly_false)]
+        writable.set_readonly(false);
+        fs::set_permissions(dest_path, writable)?;
+    }
+
+    let result = File::options()
+        .write(true)
+        .open(dest_path)
+        .and_then(|file| file.set_modified(modified));
+    fs::set_permissions(dest_path, permissions)?;
+    result?;
+
+    Ok(())
+}
+
+fn same_mtime(source_path: &Path, dest_path: &Path) -> bool {
+    match (fs::metadata(source_path), fs::metadata(dest_path)) {
+        (Ok(source), Ok(dest)) => {
+            dest.is_file()
+                && source.modified().ok().is_some()
+                && source.modified().ok() == dest.modified().ok()
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn set_mtime(path: &Path, modified: SystemTime) {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn read_only_sources_are_synced() {
+        let source = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let asset = source.path().join("asset.png");
+        fs::write(&asset, "asset").unwrap();
+
+        let mut permissions = fs::metadata(&asset).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&asset, permissions).unwrap();
+
+        let staging = || {
+            let mut staging = Staging::new(root.path());
+            staging.add_file(Path::new("res/asset.png"), &asset);
+            staging
+        };
+
+        staging().sync().unwrap();
+        set_mtime_of_read_only(&asset, SystemTime::now() + Duration::from_secs(60));
+        let report = staging().sync().unwrap();
+
+        let dest = root.path().join("res/asset.png");
+        assert_eq!(report.written, vec![PathBuf::from("res/asset.png")]);
+        assert!(fs::metadata(&dest).unwrap().permissions().readonly());
+        assert_eq!(
+            fs::metadata(&dest).unwrap().modified().unwrap(),
+            fs::metadata(&asset).unwrap().modified().unwrap()
+        );
+    }
+
+    fn set_mtime_of_read_only(path: &Path, modified: SystemTime) {
+        let permissions = fs::metadata(path).unwrap().permissions();
+        let mut writable = permissions.clone();
+        #[allow(clippy::permissions_set_readonly_false)]
+        writable.set_readonly(false);
+        fs::set_permissions(path, writable).unwrap();
+        set_mtime(path, modified);
+        fs::set_permissions(path, permissions).unwrap();
+    }
+
+    #[test]
+    fn sync_copies_only_the_changed_files_and_removes_the_stale_ones() {
+        let source = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let icon = source.path().join("icon.png");
+        let bin = source.path().join("app");
+        fs::write(&icon, "icon").unwrap();
+        fs::write(&bin, "bin").unwrap();
+
+        let staging = || {
+            let mut staging = Staging::new(root.path());
+            staging.keep(Path::new("out"));
+            staging.keep(Path::new("author-signature.xml"));
+            staging.add_file(Path::new("shared/res/icon.png"), &icon);
+            staging.add_file(Path::new("bin/app"), &bin);
+            staging.add_content(Path::new("tizen-manifest.xml"), b"<manifest/>");
+            staging.add_empty_dir(Path::new("data"));
+            staging
+        };
+
+        let report = staging().sync().unwrap();
+        assert_eq!(report.written.len(), 3);
+        assert_eq!(report.unchanged, 0);
+        assert_eq!(report.removed, 0);
+        assert!(root.path().join("data").is_dir());
+        assert_eq!(
+            fs::read_to_string(root.path().join("shared/res/icon.png")).unwrap(),
+            "icon"
+        );
+
+        fs::create_dir_all(root.path().join("out")).unwrap();
+        fs::write(root.path().join("out/app.tpk"), "tpk").unwrap();
+        fs::write(root.path().join("author-signature.xml"), "signature").unwrap();
+        fs::create_dir_all(root.path().join("lib/old")).unwrap();
+        fs::write(root.path().join("lib/old/libold.so"), "old").unwrap();
+        fs::write(root.path().join("bin/old"), "old").unwrap();
+
+        // Same content but another mtime, only the mtime is compared for copied files
+        set_mtime(&bin, SystemTime::now() + Duration::from_secs(60));
+
+        let report = staging().sync().unwrap();
+        assert_eq!(report.written, vec![PathBuf::from("bin/app")]);
+        assert_eq!(report.unchanged, 2);
+        assert_eq!(report.removed, 2);
+        assert!(!root.path().join("lib").exists());
+        assert!(!root.path().join("bin/old").exists());
+        assert!(root.path().join("out/app.tpk").is_file());
+        assert!(root.path().join("author-signature.xml").is_file());
+        assert_eq!(
+            fs::metadata(root.path().join("bin/app"))
+                .unwrap()
+                .modified()
+                .unwrap(),
+            fs::metadata(&bin).unwrap().modified().unwrap()
+        );
+
+        let report = staging().sync().unwrap();
+        assert!(report.written.is_empty());
+        assert_eq!(report.unchanged, 3);
+        assert_eq!(report.removed, 0);
+    }
+}
@@ -0,0 +1,388 @@
+use crate::error::TizenError;
+use crate::helpers::wildcard_match;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+pub struct SyncedFile {
+    pub source: PathBuf,
+    pub dest: PathBuf,
+}
+
+// Entries of tizen.sync_files, in the gitignore style:
+//   "res"                           a file or folder, packaged at the same path
+//   "res/**/*.png"                  the files matching the pattern
+//   "assets/generated -> res/img"   a file, folder or pattern packaged in another folder
+//   "!*.psd", "!res/raw/"           excludes the matching files (or folders)
+pub fn resolve_sync_files(
+    base_path: &Path,
+    entries: &[String],
+    skipped_dir: &Path,
+) -> Result<Vec<SyncedFile>, TizenError> {
+    let mut files: BTreeMap<PathBuf, PathBuf> = BTreeMap::new();
+    let mut excludes: Vec<&str> = vec![];
+
+    for entry in entries.iter().map(|entry| entry.trim()) {
+        if entry.is_empty() {
+            continue;
+        }
+
+        if let Some(exclude) = entry.strip_prefix('!') {
+            excludes.push(exclude.trim());
+            continue;
+        }
+
+        let (source, dest) = match entry.split_once("->") {
+            Some((source, dest)) => (source.trim(), Some(dest.trim().trim_matches('/'))),
+            None => (entry, None),
+        };
+
+        // The destination is inside the package, like the sources are inside the project
+        if let Some(dest) = dest {
+            if Path::new(dest)
+                .components()
+                .any(|c| matches!(c, Component::ParentDir))
+            {
+                return Err(TizenError {
+                    message: format!(
+                        "Invalid sync_files entry '{}', the destination can't go out of the package with ..",
+                        entry
+                    ),
+                });
+            }
+        }
+
+        let source = source.trim_start_matches('/');
+
+        if is_pattern(source) {
+            let base = literal_base(source);
+
+            for relative_path in list_files(base_path, &base, skipped_dir)? {
+                if matches_path(source, &relative_path) {
+                    let dest_path = match dest {
+                        Some(dest) => Path::new(dest)
+                            .join(relative_path.strip_prefix(&base).unwrap_or(&relative_path)),
+                        None => relative_path.clone(),
+                    };
+                    files.insert(dest_path, relative_path);
+                }
+            }
+        } else {
+            let source_path = base_path.join(source);
+            let dest = Path::new(dest.unwrap_or(source));
+
+            if source_path.is_file() {
+                files.insert(dest.to_path_buf(), PathBuf::from(source));
+            } else if source_path.is_dir() {
+                for relative_path in list_files(base_path, Path::new(source), skipped_dir)? {
+                    let dest_path =
+                        dest.join(relative_path.strip_prefix(source).unwrap_or(&relative_path));
+                    files.insert(dest_path, relative_path);
+                }
+            }
+        }
+    }
+
+    Ok(files
+        .into_iter()
+        .filter(|(_, source)| {
+            !excludes
+                .iter()
+                .any(|exclude| matches_exclude(exclude, source))
+        })
+        .map(|(dest, source)| SyncedFile {
+            source: base_path.join(source),
+            dest,
+        })
+        .collect())
+}
+
+fn is_pattern(value: &str) -> bool {
+    value.contains(['*', '?'])
+}
+
+// The folders before the first wildcard, where the files are searched
+fn literal_base(pattern: &str) -> PathBuf {
+    pattern
+        .split('/')
+        .take_while(|piece| !is_pattern(piece))
+        .collect::<Vec<&str>>()
+        .iter()
+        .collect()
+}
+
+fn list_files(
+    base_path: &Path,
+    relative_dir: &Path,
+    skipped_dir: &Path,
+) -> Result<Vec<PathBuf>, TizenError> {
+    let mut files: Vec<PathBuf> = vec![];
+    let mut stack: Vec<PathBuf> = vec![relative_dir.to_path_buf()];
+    // The target dir may be configured relative, with .. or through a symlink
+    let skipped_dir = canonical_path(skipped_dir);
+
+    while let Some(working_dir) = stack.pop() {
+        let full_dir = base_path.join(&working_dir);
+
+        if !full_dir.is_dir() {
+            continue;
+        }
+
+        for entry in fs::read_dir(&full_dir)? {
+            let entry = entry?;
+            let relative_path = working_dir.join(entry.file_name());
+
+            if entry.path().is_dir() {
+                if canonical_path(&entry.path()) != skipped_dir && entry.file_name() != ".git" {
+                    stack.push(relative_path);
+                }
+            } else {
+                files.push(relative_path);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// Like gitignore, a pattern without a slash matches a file or folder name at any
+// depth, and a pattern ending with a slash only matches folders
+fn matches_exclude(pattern: &str, relative_path: &Path) -> bool {
+    let (pattern, dir_only) = match pattern.strip_suffix('/') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+
+    let components: Vec<String> = relative_path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    let checked = if dir_only {
+        components.len().saturating_sub(1)
+    } else {
+        components.len()
+    };
+
+    if !pattern.contains('/') {
+        return components[..checked]
+            .iter()
+            .any(|component| wildcard_match(pattern, component));
+    }
+
+    (1..=checked).any(|len| {
+        matches_path(
+            pattern.trim_start_matches('/'),
+            &components[..len].iter().collect::<PathBuf>(),
+        )
+    })
+}
+
+// Matches a whole relative path, ** matching any number of folders
+fn matches_path(pattern: &str, relative_path: &Path) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').filter(|p| !p.is_empty()).collect();
+    let components: Vec<String> = relative_path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+
+    matches_components(&pattern, &components)
+}
+
+fn matches_components(pattern: &[&str], components: &[String]) -> bool {
+    match pattern.first() {
+        None => components.is_empty(),
+        Some(&"**") => (0..=components.len())
+            .any(|skip| matches_components(&pattern[1..], &components[skip..])),
+        Some(piece) => match components.first() {
+            Some(component) => {
+                wildcard_match(piece, component)
+                    && matches_components(&pattern[1..], &components[1..])
+            }
+            None => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project() -> tempfile::TempDir {
+        let project = tempfile::tempdir().unwrap();
+
+        for file in [
+            "res/icon.png",
+            "res/images/logo.png",
+            "res/images/logo.psd",
+            "res/raw/data.bin",
+            "res/raw/nested/raw.png",
+            "assets/generated/map.png",
+            "assets/generated/tiles/tile.png",
+            "target/debug/app.png",
+            "build/out/cached.png",
+            ".git/logo.png",
+        ] {
+            let path = project.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+
+        project
+    }
+
+    fn resolve(project: &Path, entries: &[&str], skipped_dir: &Path) -> Vec<(String, String)> {
+        let entries: Vec<String> = entries.iter().map(|entry| entry.to_string()).collect();
+
+        resolve_sync_files(project, &entries, skipped_dir)
+            .unwrap()
+            .into_iter()
+            .map(|file| {
+                (
+                    file.dest.to_string_lossy().to_string(),
+                    file.source
+                        .strip_prefix(project)
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string(),
+                )
+            })
+            .collect()
+    }
+
+    fn same_paths(paths: &[&str]) -> Vec<(String, String)> {
+        paths
+            .iter()
+            .map(|path| (path.to_string(), path.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn patterns_match_at_any_depth() {
+        let project = project();
+        let target = project.path().join("target");
+
+        assert_eq!(
+            resolve(project.path(), &["res/**/*.psd"], &target),
+            same_paths(&["res/images/logo.psd"])
+        );
+    }
+
+    #[test]
+    fn folder_excludes_skip_their_files() {
+        let project = project();
+        let target = project.path().join("target");
+
+        assert_eq!(
+            resolve(project.path(), &["res", "!res/raw/", "!*.psd"], &target),
+            same_paths(&["res/icon.png", "res/images/logo.png"])
+        );
+    }
+
+    #[test]
+    fn folders_are_packaged_in_another_folder() {
+        let project = project();
+        let target = project.path().join("target");
+
+        assert_eq!(
+            resolve(
+                project.path(),
+                &[
+                    "assets/generated -> res/images/",
+                    "assets/generated/**/*.png -> res/all"
+                ],
+                &target
+            ),
+            vec![
+                (
+                    "res/all/map.png".to_string(),
+                    "assets/generated/map.png".to_string()
+                ),
+                (
+                    "res/all/tiles/tile.png".to_string(),
+                    "assets/generated/tiles/tile.png".to_string()
+                ),
+                (
+                    "res/images/map.png".to_string(),
+                    "assets/generated/map.png".to_string()
+                ),
+                (
+                    "res/images/tiles/tile.png".to_string(),
+                    "assets/generated/tiles/tile.png".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn destinations_stay_in_the_package() {
+        let project = project();
+        let target = project.path().join("target");
+
+        assert_eq!(
+            resolve(
+                project.path(),
+                &["res/icon.png -> /shared/res/app.png"],
+                &target
+            ),
+            vec![("shared/res/app.png".to_string(), "res/icon.png".to_string())]
+        );
+        assert_eq!(
+            resolve(project.path(), &["res/raw -> /data"], &target),
+            vec![
+                ("data/data.bin".to_string(), "res/raw/data.bin".to_string()),
+                (
+                    "data/nested/raw.png".to_string(),
+                    "res/raw/nested/raw.png".to_string()
+                ),
+            ]
+        );
+
+        for entry in ["res -> ../outside", "res/*.png -> res/../../outside"] {
+            let result = resolve_sync_files(project.path(), &[entry.to_string()], &target);
+
+            assert_eq!(
+                result.err().map(|e| e.message),
+                Some(format!(
+                    "Invalid sync_files entry '{}', the destination can't go out of the package with ..",
+                    entry
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn patterns_without_a_folder_search_the_whole_project() {
+        let project = project();
+        let target = project.path().join("target");
+
+        assert_eq!(
+            resolve(project.path(), &["**/*.png", "!res/raw/"], &target),
+            same_paths(&[
+                "assets/generated/map.png",
+                "assets/generated/tiles/tile.png",
+                "build/out/cached.png",
+                "res/icon.png",
+                "res/images/logo.png",
+            ])
+        );
+    }
+
+    #[test]
+    fn the_target_dir_is_skipped_however_it_is_written() {
+        let project = project();
+        let target = project.path().join("res/../build/./out");
+
+        assert_eq!(
+            resolve(project.path(), &["**/*.png", "!res/"], &target),
+            same_paths(&[
+                "assets/generated/map.png",
+                "assets/generated/tiles/tile.png",
+                "target/debug/app.png",
+            ])
+        );
+    }
+}
@@ -1,5 +1,11 @@
 use crate::error::TizenError;
+use crate::manifest::{generate_manifest, manifest_path, tizen_version};
+use crate::sdb::{is_emulator_arch, SdbClient};
+use crate::sync_files::{resolve_sync_files, SyncedFile};
+use crate::workspace::{read_cargo_toml, CargoWorkspace};
 use clap::ArgMatches;
+use colored::*;
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::fs;
 use std::fs::read_to_string;
@@ -11,10 +17,21 @@ use toml::Value;
 
 pub struct TizenEnv {
     pub raw_config_values: Vec<ConfigValue>,
+    pub applications: Vec<TizenApplication>,
+    pub generated_manifest: Option<String>,
 
     pub base_path: PathBuf,
+    pub target_dir: PathBuf,
+    pub cargo_package: Option<String>,
     pub studio_path: PathBuf,
     pub tizen_bin: String,
+    pub sdb_bin: String,
+    pub sdb_port: u16,
+    pub device_tool: String,
+    pub target_serial: String,
+    pub em_cli_bin: String,
+    pub edje_cc_bin: String,
+    pub emulator_name: String,
     pub is_emulator: bool,
     pub api_version: String,
     pub app_profile: String,
@@ -26,19 +43,46 @@ pub struct TizenEnv {
     pub rust_linker: String,
     pub app_id: String,
     pub app_version: String,
+    #[allow(dead_code)]
     pub app_package: String,
-    pub app_exec: String,
     pub app_label: String,
-    pub app_ui_type: String,
     pub cargo_pkg_name: String,
+    pub cargo_version: String,
     pub sync_files: Vec<String>,
+    pub edc_dirs: Vec<String>,
+    pub edc_image_dirs: Vec<String>,
+    pub edc_sound_dirs: Vec<String>,
+    pub edc_font_dirs: Vec<String>,
+    pub po_dir: String,
+    pub text_domain: String,
     pub is_release: bool,
     pub security_profile: String,
+    pub packager: String,
+    pub version_sync: String,
+    pub author_cert: String,
+    pub author_password: String,
+    pub distributor_cert: String,
+    pub distributor_password: String,
+    pub pre_package_hook: String,
+    pub post_package_hook: String,
+    pub runner_targets: Vec<(String, bool)>,
 }
 
 impl TizenEnv {
-    pub fn from_cargo_config(cwd: &Path, app_m: &ArgMatches) -> Result<Self, TizenError> {
-        let config_provider = ConfigProvider::new(cwd.to_path_buf(), &app_m)?;
+    // detect_target allows is_emulator = "auto" to ask sdb for the target, commands
+    // not using the triple skip it and fall back to the device
+    pub fn from_cargo_config(
+        cwd: &Path,
+        app_m: &ArgMatches,
+        detect_target: bool,
+    ) -> Result<Self, TizenError> {
+        let workspace = CargoWorkspace::discover(cwd, app_m.value_of("package"))?;
+        let config_provider = ConfigProvider::new(
+            workspace.package_path.clone(),
+            workspace.root.clone(),
+            app_m,
+            detect_target,
+        )?;
 
         let is_release = app_m.is_present("release");
         let studio_path = config_provider.get_value(&ConfigType::StudioPath)?;
@@ -58,10 +102,32 @@ impl TizenEnv {
         let app_package = config_provider.get_value(&ConfigType::AppPackage)?;
         let app_exec = config_provider.get_value(&ConfigType::AppExec)?;
         let tizen_bin = config_provider.get_value(&ConfigType::TizenBin)?;
+        let sdb_bin = config_provider.get_value(&ConfigType::SdbBin)?;
+        let sdb_port = config_provider.get_value(&ConfigType::SdbPort)?;
+        let device_tool = config_provider.get_value(&ConfigType::DeviceTool)?;
+        let target_serial = config_provider.get_value(&ConfigType::TargetSerial)?;
+        let em_cli_bin = config_provider.get_value(&ConfigType::EmCliBin)?;
+        let emulator_name = config_provider.get_value(&ConfigType::EmulatorName)?;
         let app_label = config_provider.get_value(&ConfigType::AppLabel)?;
         let app_ui_type = config_provider.get_value(&ConfigType::AppUiType)?;
         let sync_files = config_provider.get_value(&ConfigType::SyncFiles)?;
+        let edje_cc_bin = config_provider.get_value(&ConfigType::EdjeCcBin)?;
+        let edc_dirs = config_provider.get_value(&ConfigType::EdcDirs)?;
+        let edc_image_dirs = config_provider.get_value(&ConfigType::EdcImageDirs)?;
+        let edc_sound_dirs = config_provider.get_value(&ConfigType::EdcSoundDirs)?;
+        let edc_font_dirs = config_provider.get_value(&ConfigType::EdcFontDirs)?;
+        let po_dir = config_provider.get_value(&ConfigType::PoDir)?;
+        let text_domain = config_provider.get_value(&ConfigType::TextDomain)?;
         let security_profile = config_provider.get_value(&ConfigType::SecurityProfile)?;
+        let packager = config_provider.get_value(&ConfigType::Packager)?;
+        let version_sync = config_provider.get_value(&ConfigType::VersionSync)?;
+        let target_dir = config_provider.get_value(&ConfigType::TargetDir)?;
+        let author_cert = config_provider.get_value(&ConfigType::AuthorCert)?;
+        let author_password = config_provider.get_value(&ConfigType::AuthorPassword)?;
+        let distributor_cert = config_provider.get_value(&ConfigType::DistributorCert)?;
+        let distributor_password = config_provider.get_value(&ConfigType::DistributorPassword)?;
+        let pre_package_hook = config_provider.get_value(&ConfigType::PrePackageHook)?;
+        let post_package_hook = config_provider.get_value(&ConfigType::PostPackageHook)?;
 
         let cargo_pkg_name = match config_provider.get_cargo_value("package.name") {
             Some(s) => s,
@@ -72,14 +138,25 @@ impl TizenEnv {
             }
         };
 
+        let applications = config_provider.get_applications();
+        let runner_targets = config_provider.get_runner_targets()?;
+
+        if applications.is_empty() {
+            return Err(TizenError {
+                message: "No application found in tizen-manifest.xml".to_string(),
+            });
+        }
+
         let sync_files_array: Vec<&str> = sync_files.value.split(',').collect();
         let sync_files_array: Vec<String> =
             sync_files_array.iter().map(|s| s.to_string()).collect();
 
         Ok(Self {
-            base_path: PathBuf::from(cwd),
+            target_dir: workspace.package_path.join(&target_dir.value),
+            base_path: workspace.package_path,
+            cargo_package: app_m.value_of("package").map(|v| v.to_string()),
             studio_path: PathBuf::from(&studio_path.value),
-            is_emulator: str_to_bool(&studio_path.value),
+            is_emulator: str_to_bool(&is_emulator.value),
             api_version: api_version.value.clone(),
             app_profile: app_profile.value.clone(),
             rootstrap_path: PathBuf::from(&rootstrap_path.value),
@@ -91,14 +168,37 @@ impl TizenEnv {
             app_id: app_id.value.clone(),
             app_version: app_version.value.clone(),
             app_package: app_package.value.clone(),
-            app_exec: app_exec.value.clone(),
             tizen_bin: tizen_bin.value.clone(),
+            sdb_bin: sdb_bin.value.clone(),
+            sdb_port: parse_sdb_port(&sdb_port.value)?,
+            device_tool: device_tool.value.clone(),
+            target_serial: target_serial.value.clone(),
+            em_cli_bin: em_cli_bin.value.clone(),
+            emulator_name: emulator_name.value.clone(),
             cargo_pkg_name,
+            cargo_version: config_provider.cargo_version.clone().unwrap_or_default(),
             sync_files: sync_files_array,
+            edje_cc_bin: edje_cc_bin.value.clone(),
+            edc_dirs: split_list(&edc_dirs.value),
+            edc_image_dirs: split_list(&edc_image_dirs.value),
+            edc_sound_dirs: split_list(&edc_sound_dirs.value),
+            edc_font_dirs: split_list(&edc_font_dirs.value),
+            po_dir: po_dir.value.clone(),
+            text_domain: text_domain.value.clone(),
             app_label: app_label.value.clone(),
-            app_ui_type: app_ui_type.value.clone(),
             security_profile: security_profile.value.clone(),
+            packager: packager.value.clone(),
+            version_sync: version_sync.value.clone(),
+            author_cert: author_cert.value.clone(),
+            author_password: author_password.value.clone(),
+            distributor_cert: distributor_cert.value.clone(),
+            distributor_password: distributor_password.value.clone(),
+            pre_package_hook: pre_package_hook.value.clone(),
+            post_package_hook: post_package_hook.value.clone(),
+            runner_targets,
             is_release,
+            applications,
+            generated_manifest: config_provider.generated_manifest.clone(),
             raw_config_values: vec![
                 studio_path,
                 is_emulator,
@@ -118,9 +218,31 @@ impl TizenEnv {
                 app_package,
                 app_exec,
                 tizen_bin,
+                sdb_bin,
+                sdb_port,
+                device_tool,
+                target_serial,
+                em_cli_bin,
+                emulator_name,
                 app_label,
                 sync_files,
+                edje_cc_bin,
+                edc_dirs,
+                edc_image_dirs,
+                edc_sound_dirs,
+                edc_font_dirs,
+                po_dir,
+                text_domain,
                 app_ui_type,
+                packager,
+                version_sync,
+                target_dir,
+                author_cert,
+                author_password,
+                distributor_cert,
+                distributor_password,
+                pre_package_hook,
+                post_package_hook,
             ],
         })
     }
@@ -156,9 +278,15 @@ impl TizenEnv {
         envs
     }
 
+    pub fn manifest_content(&self) -> Result<String, TizenError> {
+        match &self.generated_manifest {
+            Some(content) => Ok(content.clone()),
+            None => Ok(fs::read_to_string(manifest_path(&self.base_path))?),
+        }
+    }
+
     pub fn rust_output_dir(&self) -> PathBuf {
-        let mut out_path = self.base_path.clone();
-        out_path.push("target");
+        let mut out_path = self.target_dir.clone();
         out_path.push(&self.rust_triple);
         out_path.push(if self.is_release { "release" } else { "debug" });
 
@@ -181,15 +309,57 @@ impl TizenEnv {
         out_path
     }
 
+    // The cargo target folder is never walked by the sync_files patterns
+    pub fn synced_files(&self) -> Result<Vec<SyncedFile>, TizenError> {
+        resolve_sync_files(&self.base_path, &self.sync_files, &self.target_dir)
+    }
+
+    // With version_sync = "rewrite" the staged manifest gets the Cargo.toml version
+    pub fn package_version(&self) -> String {
+        match self.version_sync.as_str() {
+            "rewrite" => {
+                tizen_version(&self.cargo_version).unwrap_or_else(|_| self.app_version.clone())
+            }
+            _ => self.app_version.clone(),
+        }
+    }
+
     pub fn tpk_name(&self) -> String {
         format!(
             "{}-{}-{}.tpk",
             &self.app_id,
-            &self.app_version,
+            &self.package_version(),
             &self.arch_alias()
         )
     }
 
+    pub fn find_application(&self, app_id: Option<&str>) -> Result<&TizenApplication, TizenError> {
+        let application = match app_id {
+            Some(app_id) => self.applications.iter().find(|a| a.app_id == app_id),
+            None => self
+                .applications
+                .iter()
+                .find(|a| a.app_type == ApplicationType::Ui)
+                .or_else(|| self.applications.iter().find(|a| a.app_type.can_launch()))
+                .or_else(|| self.applications.first()),
+        };
+
+        match application {
+            Some(application) => Ok(application),
+            None => Err(TizenError {
+                message: format!(
+                    "Application '{}' not found in manifest, available applications: {}",
+                    app_id.unwrap_or(""),
+                    self.applications
+                        .iter()
+                        .map(|a| a.app_id.clone())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+            }),
+        }
+    }
+
     pub fn arch_alias(&self) -> String {
         if self.tizen_triple.contains("arm") {
             "arm".to_string()
@@ -212,12 +382,96 @@ impl TizenEnv {
                 complete_path.to_str().unwrap().to_string()
             })
     }
+
+    pub fn gdb_bin(&self) -> Option<PathBuf> {
+        fs::read_dir(&self.toolchain_path).ok().and_then(|v| {
+            v.filter_map(|entry_result| entry_result.ok())
+                .map(|entry| entry.path())
+                .find(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .map(|name| name.ends_with("-gdb"))
+                        .unwrap_or(false)
+                })
+        })
+    }
+}
+
+#[derive(PartialEq)]
+pub enum ApplicationType {
+    Ui,
+    Service,
+    Widget,
+    Watch,
+    Unsupported(String),
+}
+
+impl ApplicationType {
+    pub fn from_element(element: &str) -> Self {
+        match element {
+            "ui-application" => ApplicationType::Ui,
+            "service-application" => ApplicationType::Service,
+            "widget-application" => ApplicationType::Widget,
+            "watch-application" => ApplicationType::Watch,
+            _ => ApplicationType::Unsupported(element.to_string()),
+        }
+    }
+
+    pub fn element(&self) -> &str {
+        match self {
+            ApplicationType::Ui => "ui-application",
+            ApplicationType::Service => "service-application",
+            ApplicationType::Widget => "widget-application",
+            ApplicationType::Watch => "watch-application",
+            ApplicationType::Unsupported(element) => element,
+        }
+    }
+
+    pub fn can_launch(&self) -> bool {
+        matches!(self, ApplicationType::Ui | ApplicationType::Service)
+    }
+}
+
+pub struct TizenApplication {
+    pub app_type: ApplicationType,
+    pub app_id: String,
+    pub exec: String,
+    pub ui_type: String,
+}
+
+impl TizenApplication {
+    pub fn exec_name(&self) -> String {
+        match Path::new(&self.exec).file_name() {
+            Some(file_name) => file_name.to_string_lossy().to_string(),
+            None => self.exec.clone(),
+        }
+    }
+}
+
+fn parse_sdb_port(val: &str) -> Result<u16, TizenError> {
+    match val.parse::<u16>() {
+        Ok(port) => Ok(port),
+        Err(_) => Err(TizenError {
+            message: format!("Invalid sdb port {}", val),
+        }),
+    }
+}
+
+fn split_list(val: &str) -> Vec<String> {
+    val.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
 }
 
 fn str_to_bool(val: &str) -> bool {
     val == "1" || val == "true"
 }
 
+pub fn manifest_key(config_type: &ConfigType) -> Option<String> {
+    ConfigProvider::get_manifest_key(config_type)
+}
+
 #[derive(Copy, Clone)]
 pub enum ConfigType {
     StudioPath,
@@ -237,10 +491,32 @@ pub enum ConfigType {
     ToolchainPath,
     RustLinker,
     TizenBin,
+    SdbBin,
+    SdbPort,
+    DeviceTool,
+    TargetSerial,
+    EmCliBin,
+    EmulatorName,
     AppLabel,
     AppUiType,
     SyncFiles,
+    EdjeCcBin,
+    EdcDirs,
+    EdcImageDirs,
+    EdcSoundDirs,
+    EdcFontDirs,
+    PoDir,
+    TextDomain,
     SecurityProfile,
+    Packager,
+    VersionSync,
+    TargetDir,
+    AuthorCert,
+    AuthorPassword,
+    DistributorCert,
+    DistributorPassword,
+    PrePackageHook,
+    PostPackageHook,
 }
 
 pub enum ConfigFrom {
@@ -249,9 +525,11 @@ pub enum ConfigFrom {
     Manifest,
     Arg,
     Default,
+    Detected,
 }
 
 pub struct ConfigValue {
+    #[allow(dead_code)]
     pub config_type: ConfigType,
     pub from: ConfigFrom,
     pub value: String,
@@ -262,32 +540,155 @@ pub struct ConfigValue {
 
 struct ConfigProvider<'a> {
     arg_matches: &'a ArgMatches<'a>,
-    cargo_files: Vec<Value>,
+    workspace_root: PathBuf,
+    cargo_files: Vec<(PathBuf, Value)>,
     cargo_build_file: Value,
     cargo_default_file: Value,
     manifest_document: Package,
+    generated_manifest: Option<String>,
+    cargo_version: Option<String>,
+    detect_target: bool,
+    detected_is_emulator: Cell<Option<Option<bool>>>,
 }
 
 impl<'a> ConfigProvider<'a> {
-    fn new(base_path: PathBuf, arg_matches: &'a ArgMatches<'a>) -> Result<Self, TizenError> {
-        let mut manifest_path = base_path.clone();
-        manifest_path.push("tizen-manifest.xml");
+    fn new(
+        base_path: PathBuf,
+        workspace_root: PathBuf,
+        arg_matches: &'a ArgMatches<'a>,
+        detect_target: bool,
+    ) -> Result<Self, TizenError> {
+        let manifest_path = manifest_path(&base_path);
 
         let cargo_files = Self::get_cargo_config_files(&base_path);
-        let cargo_build_file = Self::get_cargo_build_file(&base_path)?;
+        let cargo_build_file = read_cargo_toml(&base_path)?;
         let cargo_default_file = Self::get_cargo_default_file();
 
+        let cargo_version = Self::get_cargo_version(&cargo_build_file, &workspace_root);
+
+        let generated_manifest = match manifest_path.exists() {
+            true => None,
+            false => generate_manifest(&cargo_build_file, cargo_version.as_deref())?,
+        };
+
+        let manifest_content = match &generated_manifest {
+            Some(content) => content.clone(),
+            None => match read_to_string(&manifest_path) {
+                Ok(content) => content,
+                Err(_) => {
+                    return Err(TizenError {
+                        message: format!(
+                            "File does not exists {}, create it or add [package.metadata.tizen] to Cargo.toml",
+                            manifest_path.to_str().unwrap_or("")
+                        ),
+                    })
+                }
+            },
+        };
+
         Ok(Self {
             arg_matches,
+            workspace_root,
             cargo_files,
             cargo_build_file,
             cargo_default_file,
-            manifest_document: parser::parse(&std::fs::read_to_string(manifest_path.as_path())?)?,
+            manifest_document: parser::parse(&manifest_content)?,
+            generated_manifest,
+            cargo_version,
+            detect_target,
+            detected_is_emulator: Cell::new(None),
         })
     }
 
     fn get_value(&self, config_type: &ConfigType) -> Result<ConfigValue, TizenError> {
-        self.get_custom_value(&config_type, self.get_dynamic_key(&config_type))
+        let config_value = self.get_custom_value(config_type, self.get_dynamic_key(config_type))?;
+
+        match config_type {
+            ConfigType::IsEmulator if config_value.value == "auto" => {
+                let detected = match self.detect_target {
+                    true => self.detect_is_emulator(),
+                    false => None,
+                };
+
+                Ok(match detected {
+                    Some(is_emulator) => ConfigValue {
+                        from: ConfigFrom::Detected,
+                        value: is_emulator.to_string(),
+                        ..config_value
+                    },
+                    None => ConfigValue {
+                        from: ConfigFrom::Default,
+                        value: "false".to_string(),
+                        ..config_value
+                    },
+                })
+            }
+            _ => Ok(config_value),
+        }
+    }
+
+    // is_emulator = "auto" asks the selected target for its cpu arch, once
+    fn detect_is_emulator(&self) -> Option<bool> {
+        if let Some(detected) = self.detected_is_emulator.get() {
+            return detected;
+        }
+
+        let detected = match self.query_target_arch() {
+            Ok(arch) => Some(is_emulator_arch(&arch)),
+            Err(error) => {
+                eprintln!(
+                    "{} {}",
+                    "Can't detect the target for is_emulator = auto, using the device:"
+                        .yellow()
+                        .bold(),
+                    error.message
+                );
+                None
+            }
+        };
+
+        self.detected_is_emulator.set(Some(detected));
+        detected
+    }
+
+    fn query_target_arch(&self) -> Result<String, TizenError> {
+        let sdb_port = self.get_value(&ConfigType::SdbPort)?.value;
+        let sdb = SdbClient::with_config(
+            parse_sdb_port(&sdb_port)?,
+            &self.get_value(&ConfigType::SdbBin)?.value,
+            &self.get_value(&ConfigType::TargetSerial)?.value,
+            None,
+            "",
+        );
+
+        let device = sdb.select_device()?;
+        sdb.arch(&device.serial)
+    }
+
+    // Rust triples of the device and of the emulator, with is_emulator for each
+    fn get_runner_targets(&self) -> Result<Vec<(String, bool)>, TizenError> {
+        let device_triple = self.get_value(&ConfigType::DeviceTriple)?.value;
+        let emulator_triple = self
+            .get_custom_value(
+                &ConfigType::EmulatorTriple,
+                Some("tizen.emulator.tizen_triple".to_string()),
+            )?
+            .value;
+
+        let mut runner_targets: Vec<(String, bool)> = vec![];
+
+        for (tizen_triple, is_emulator) in [(device_triple, false), (emulator_triple, true)] {
+            let rust_triple = self.get_custom_value(
+                &ConfigType::RustTriple,
+                Some(format!("tizen.target.{}.rust_triple", &tizen_triple)),
+            )?;
+
+            if !runner_targets.iter().any(|(t, _)| t == &rust_triple.value) {
+                runner_targets.push((rust_triple.value, is_emulator));
+            }
+        }
+
+        Ok(runner_targets)
     }
 
     fn get_dynamic_key(&self, config_type: &ConfigType) -> Option<String> {
@@ -349,7 +750,7 @@ impl<'a> ConfigProvider<'a> {
             None => Self::get_cargo_key(config_type),
         };
 
-        let env_key = match Self::get_env_key(&config_type, cargo_key.clone()) {
+        let env_key = match Self::get_env_key(config_type, cargo_key.clone()) {
             Some(env_key) => env_key,
             None => {
                 return Err(TizenError {
@@ -358,7 +759,7 @@ impl<'a> ConfigProvider<'a> {
             }
         };
 
-        let manifest_key = Self::get_manifest_key(&config_type);
+        let manifest_key = Self::get_manifest_key(config_type);
 
         let base_config_value = ConfigValue {
             config_type: *config_type,
@@ -369,6 +770,18 @@ impl<'a> ConfigProvider<'a> {
             manifest_key: manifest_key.clone(),
         };
 
+        // --serial and -e pick the target for this run, so they win over the configured
+        // one, an "auto" is_emulator included
+        if let ConfigType::TargetSerial | ConfigType::IsEmulator = config_type {
+            if let Some(str_value) = self.get_arg_value(config_type) {
+                return Ok(ConfigValue {
+                    from: ConfigFrom::Arg,
+                    value: str_value,
+                    ..base_config_value
+                });
+            }
+        }
+
         if let Ok(str_value) = std::env::var(&env_key) {
             return Ok(ConfigValue {
                 from: ConfigFrom::Env,
@@ -378,7 +791,7 @@ impl<'a> ConfigProvider<'a> {
         }
 
         if let Some(manifest_key) = &manifest_key {
-            if let Some(str_value) = self.get_manifest_value(&manifest_key) {
+            if let Some(str_value) = self.get_manifest_value(manifest_key) {
                 return Ok(ConfigValue {
                     from: ConfigFrom::Manifest,
                     value: str_value,
@@ -388,7 +801,7 @@ impl<'a> ConfigProvider<'a> {
         }
 
         if let Some(cargo_key) = &cargo_key {
-            if let Some(str_value) = self.get_cargo_value(&cargo_key) {
+            if let Some(str_value) = self.get_cargo_value(cargo_key) {
                 return Ok(ConfigValue {
                     from: ConfigFrom::Cargo,
                     value: str_value,
@@ -397,7 +810,7 @@ impl<'a> ConfigProvider<'a> {
             }
         }
 
-        if let Some(str_value) = self.get_arg_value(&config_type) {
+        if let Some(str_value) = self.get_arg_value(config_type) {
             return Ok(ConfigValue {
                 from: ConfigFrom::Arg,
                 value: str_value,
@@ -405,7 +818,7 @@ impl<'a> ConfigProvider<'a> {
             });
         }
 
-        if let Some(str_value) = self.get_default_value(&config_type, &cargo_key) {
+        if let Some(str_value) = self.get_default_value(config_type, &cargo_key) {
             return Ok(ConfigValue {
                 from: ConfigFrom::Default,
                 value: str_value,
@@ -437,10 +850,7 @@ impl<'a> ConfigProvider<'a> {
         let document = self.manifest_document.as_document();
 
         let expression = match Factory::new().build(path) {
-            Ok(e) => match e {
-                Some(e) => e,
-                None => return None,
-            },
+            Ok(e) => e?,
             Err(_) => return None,
         };
 
@@ -456,14 +866,40 @@ impl<'a> ConfigProvider<'a> {
         }
     }
 
+    fn get_applications(&self) -> Vec<TizenApplication> {
+        let document = self.manifest_document.as_document();
+
+        let root = match document
+            .root()
+            .children()
+            .into_iter()
+            .find_map(|c| c.element())
+        {
+            Some(root) => root,
+            None => return vec![],
+        };
+
+        root.children()
+            .into_iter()
+            .filter_map(|c| c.element())
+            .filter(|e| e.attribute_value("appid").is_some())
+            .map(|e| TizenApplication {
+                app_type: ApplicationType::from_element(e.name().local_part()),
+                app_id: e.attribute_value("appid").unwrap_or("").to_string(),
+                exec: e.attribute_value("exec").unwrap_or("").to_string(),
+                ui_type: e.attribute_value("type").unwrap_or("capp").to_string(),
+            })
+            .collect()
+    }
+
     fn get_cargo_value(&self, key: &str) -> Option<String> {
-        if let Some(result_str) = Self::get_toml_str(&self.cargo_build_file, &key) {
+        if let Some(result_str) = Self::get_toml_str(&self.cargo_build_file, key) {
             return Some(result_str);
         }
 
         if !key.starts_with("package.") {
-            for cargo_file in self.cargo_files.iter() {
-                if let Some(result_str) = Self::get_toml_str(&cargo_file, &key) {
+            for (_, cargo_file) in self.cargo_files.iter() {
+                if let Some(result_str) = Self::get_toml_str(cargo_file, key) {
                     return Some(result_str);
                 }
             }
@@ -472,12 +908,27 @@ impl<'a> ConfigProvider<'a> {
         None
     }
 
+    // package.version may be inherited with version.workspace = true
+    fn get_cargo_version(cargo_build_file: &Value, workspace_root: &Path) -> Option<String> {
+        let version = cargo_build_file
+            .get("package")
+            .and_then(|p| p.get("version"))?;
+
+        match version.as_str() {
+            Some(version) => Some(version.to_string()),
+            None => read_cargo_toml(workspace_root)
+                .ok()
+                .and_then(|root| Self::get_toml_str(&root, "workspace.package.version")),
+        }
+    }
+
     fn get_arg_value(&self, config_type: &ConfigType) -> Option<String> {
         match config_type {
             ConfigType::IsEmulator => match self.arg_matches.is_present("emulator") {
                 true => Some("true".to_string()),
                 _ => None,
             },
+            ConfigType::TargetSerial => self.arg_matches.value_of("serial").map(|s| s.to_string()),
             _ => None,
         }
     }
@@ -487,16 +938,13 @@ impl<'a> ConfigProvider<'a> {
         config_type: &ConfigType,
         cargo_key: &Option<String>,
     ) -> Option<String> {
-        match cargo_key {
-            Some(str_val) => {
-                if let Some(result_str) = Self::get_toml_str(&self.cargo_default_file, &str_val) {
-                    return Some(result_str);
-                }
+        if let Some(str_val) = cargo_key {
+            if let Some(result_str) = Self::get_toml_str(&self.cargo_default_file, str_val) {
+                return Some(result_str);
             }
-            None => (),
         }
 
-        self.get_default_computed_value(&config_type).ok()
+        self.get_default_computed_value(config_type).ok()
     }
 
     fn get_default_computed_value(&self, config_type: &ConfigType) -> Result<String, TizenError> {
@@ -581,6 +1029,40 @@ impl<'a> ConfigProvider<'a> {
                     }),
                 }
             }
+            ConfigType::EmCliBin => {
+                let tizen_studio_path = self.get_value(&ConfigType::StudioPath)?.value;
+
+                let mut path = PathBuf::from(tizen_studio_path);
+                path.push("tools");
+                path.push("emulator");
+                path.push("bin");
+                path.push("em-cli");
+
+                match path.to_str() {
+                    Some(str_value) => Ok(str_value.to_string()),
+                    None => Err(TizenError {
+                        message: "Can't get path".to_string(),
+                    }),
+                }
+            }
+            // Tizen apps bind their gettext domain to the package id by convention
+            ConfigType::TextDomain => Ok(self.get_value(&ConfigType::AppPackage)?.value),
+            ConfigType::EdjeCcBin => {
+                let tizen_studio_path = self.get_value(&ConfigType::StudioPath)?.value;
+
+                let mut path = PathBuf::from(tizen_studio_path);
+                path.push("tools");
+                path.push("efl-tools");
+                path.push("bin");
+                path.push("edje_cc");
+
+                match path.to_str() {
+                    Some(str_value) => Ok(str_value.to_string()),
+                    None => Err(TizenError {
+                        message: "Can't get path".to_string(),
+                    }),
+                }
+            }
             ConfigType::TizenBin => {
                 let tizen_studio_path = self.get_value(&ConfigType::StudioPath)?.value;
 
@@ -597,6 +1079,56 @@ impl<'a> ConfigProvider<'a> {
                     }),
                 }
             }
+            ConfigType::SdbBin => {
+                let tizen_studio_path = self.get_value(&ConfigType::StudioPath)?.value;
+
+                let mut path = PathBuf::from(tizen_studio_path);
+                path.push("tools");
+                path.push("sdb");
+
+                match path.to_str() {
+                    Some(str_value) => Ok(str_value.to_string()),
+                    None => Err(TizenError {
+                        message: "Can't get path".to_string(),
+                    }),
+                }
+            }
+            ConfigType::DistributorCert => {
+                let tizen_studio_path = self.get_value(&ConfigType::StudioPath)?.value;
+
+                let mut path = PathBuf::from(tizen_studio_path);
+                path.push("tools");
+                path.push("certificate-generator");
+                path.push("certificates");
+                path.push("distributor");
+                path.push("tizen-distributor-signer.p12");
+
+                match path.to_str() {
+                    Some(str_value) => Ok(str_value.to_string()),
+                    None => Err(TizenError {
+                        message: "Can't get path".to_string(),
+                    }),
+                }
+            }
+            ConfigType::TargetDir => {
+                // Relative paths in .cargo/config are relative to the folder containing .cargo
+                let configured = self
+                    .cargo_files
+                    .iter()
+                    .find_map(|(config_dir, cargo_file)| {
+                        Self::get_toml_str(cargo_file, "build.target-dir")
+                            .map(|target_dir| config_dir.join(target_dir))
+                    });
+
+                let path = configured.unwrap_or_else(|| self.workspace_root.join("target"));
+
+                match path.to_str() {
+                    Some(str_value) => Ok(str_value.to_string()),
+                    None => Err(TizenError {
+                        message: "Can't get path".to_string(),
+                    }),
+                }
+            }
             ConfigType::RustLinker => {
                 let toolchain_path = self.get_value(&ConfigType::ToolchainPath)?.value;
                 let selected_triple = self.get_value(&ConfigType::SelectedTriple)?.value;
@@ -633,9 +1165,30 @@ impl<'a> ConfigProvider<'a> {
             ConfigType::SelectedTriple => Some("tizen.selected_triple".to_string()),
             ConfigType::Toolchain => Some("tizen.toolchain".to_string()),
             ConfigType::TizenBin => Some("tizen.bin_path".to_string()),
+            ConfigType::SdbBin => Some("tizen.sdb_path".to_string()),
+            ConfigType::SdbPort => Some("tizen.sdb_port".to_string()),
+            ConfigType::DeviceTool => Some("tizen.device_tool".to_string()),
+            ConfigType::TargetSerial => Some("tizen.target_serial".to_string()),
+            ConfigType::EmCliBin => Some("tizen.em_cli_path".to_string()),
+            ConfigType::EmulatorName => Some("tizen.emulator.name".to_string()),
             ConfigType::SyncFiles => Some("tizen.sync_files".to_string()),
+            ConfigType::EdjeCcBin => Some("tizen.edje_cc_path".to_string()),
+            ConfigType::EdcDirs => Some("tizen.edje.edc_dirs".to_string()),
+            ConfigType::EdcImageDirs => Some("tizen.edje.image_dirs".to_string()),
+            ConfigType::EdcSoundDirs => Some("tizen.edje.sound_dirs".to_string()),
+            ConfigType::EdcFontDirs => Some("tizen.edje.font_dirs".to_string()),
+            ConfigType::PoDir => Some("tizen.i18n.po_dir".to_string()),
+            ConfigType::TextDomain => Some("tizen.i18n.domain".to_string()),
             ConfigType::AppLabel => Some("tizen.app_label".to_string()),
             ConfigType::AppUiType => Some("tizen.app_ui_type".to_string()),
+            ConfigType::Packager => Some("tizen.packager".to_string()),
+            ConfigType::VersionSync => Some("tizen.version_sync".to_string()),
+            ConfigType::AuthorCert => Some("tizen.sign.author_cert".to_string()),
+            ConfigType::AuthorPassword => Some("tizen.sign.author_password".to_string()),
+            ConfigType::DistributorCert => Some("tizen.sign.distributor_cert".to_string()),
+            ConfigType::DistributorPassword => Some("tizen.sign.distributor_password".to_string()),
+            ConfigType::PrePackageHook => Some("tizen.hooks.pre_package".to_string()),
+            ConfigType::PostPackageHook => Some("tizen.hooks.post_package".to_string()),
             _ => None,
         }
     }
@@ -648,6 +1201,7 @@ impl<'a> ConfigProvider<'a> {
                 ConfigType::AppVersion => Some("TIZEN_APP_VERSION".to_string()),
                 ConfigType::AppPackage => Some("TIZEN_APP_PACKAGE".to_string()),
                 ConfigType::AppExec => Some("TIZEN_APP_EXEC".to_string()),
+                ConfigType::TargetDir => Some("CARGO_TARGET_DIR".to_string()),
                 _ => None,
             },
         };
@@ -657,46 +1211,18 @@ impl<'a> ConfigProvider<'a> {
 
     fn get_manifest_key(config_type: &ConfigType) -> Option<String> {
         match config_type {
-            ConfigType::AppId => Some("/ns:manifest/ns:ui-application/@appid".to_string()),
+            ConfigType::AppId => Some("(/ns:manifest/*[@appid])[1]/@appid".to_string()),
             ConfigType::AppVersion => Some("/ns:manifest/@version".to_string()),
             ConfigType::ApiVersion => Some("/ns:manifest/@api-version".to_string()),
             ConfigType::AppPackage => Some("/ns:manifest/@package".to_string()),
-            ConfigType::AppExec => Some("/ns:manifest/ns:ui-application/@exec".to_string()),
-            ConfigType::AppLabel => Some("/ns:manifest/ns:ui-application/ns:label".to_string()),
+            ConfigType::AppExec => Some("(/ns:manifest/*[@appid])[1]/@exec".to_string()),
+            ConfigType::AppLabel => Some("(/ns:manifest/*[@appid])[1]/ns:label".to_string()),
             ConfigType::AppProfile => Some("/ns:manifest/ns:profile/@name".to_string()),
-            ConfigType::AppUiType => Some("/ns:manifest/ns:ui-application/@type".to_string()),
+            ConfigType::AppUiType => Some("(/ns:manifest/*[@appid])[1]/@type".to_string()),
             _ => None,
         }
     }
 
-    fn get_cargo_build_file(base_path: &Path) -> Result<Value, TizenError> {
-        let mut cargo_build_path = PathBuf::from(base_path);
-        cargo_build_path.push("Cargo.toml");
-
-        match cargo_build_path.exists() {
-            true => match read_to_string(&cargo_build_path) {
-                Ok(content) => match content.parse::<Value>() {
-                    Ok(toml_value) => Ok(toml_value),
-                    Err(_) => Err(TizenError {
-                        message: format!(
-                            "Can't parse {}",
-                            &cargo_build_path.to_str().unwrap_or("")
-                        ),
-                    }),
-                },
-                Err(_) => Err(TizenError {
-                    message: format!("Can't read {}", &cargo_build_path.to_str().unwrap_or("")),
-                }),
-            },
-            false => Err(TizenError {
-                message: format!(
-                    "File does not exists {}",
-                    &cargo_build_path.to_str().unwrap_or("")
-                ),
-            }),
-        }
-    }
-
     fn get_cargo_default_file() -> Value {
         let cargo_default_str = include_str!(concat!(
             env!("CARGO_MANIFEST_DIR"),
@@ -706,17 +1232,17 @@ impl<'a> ConfigProvider<'a> {
         cargo_default_str.parse::<Value>().unwrap()
     }
 
-    fn get_cargo_config_files(base_path: &Path) -> Vec<Value> {
+    fn get_cargo_config_files(base_path: &Path) -> Vec<(PathBuf, Value)> {
         let mut config_path = PathBuf::from(base_path);
 
         config_path.push(".cargo");
         config_path.push("config");
         config_path.set_extension("toml");
 
-        let mut first_vector: Vec<Value> = match config_path.exists() {
+        let mut first_vector: Vec<(PathBuf, Value)> = match config_path.exists() {
             true => match read_to_string(&config_path) {
                 Ok(content) => match content.parse::<Value>() {
-                    Ok(toml_value) => vec![toml_value],
+                    Ok(toml_value) => vec![(PathBuf::from(base_path), toml_value)],
                     Err(_) => vec![],
                 },
                 Err(_) => vec![],
@@ -726,7 +1252,7 @@ impl<'a> ConfigProvider<'a> {
 
         let mut new_path = PathBuf::from(base_path);
 
-        let last_vector: Vec<Value> = match new_path.pop() {
+        let last_vector: Vec<(PathBuf, Value)> = match new_path.pop() {
             true => match Self::home_dir() {
                 Some(home_path) => {
                     if home_path == new_path {
@@ -752,17 +1278,12 @@ impl<'a> ConfigProvider<'a> {
     }
 
     fn get_toml_str(toml_value: &Value, key: &str) -> Option<String> {
-        let result_opt =
-            key.split('.').fold(
-                Some(toml_value),
-                |old_value_opt, piece| match old_value_opt {
-                    Some(old_value) => old_value.get(piece),
-                    _ => None,
-                },
-            );
+        let result_opt = key
+            .split('.')
+            .try_fold(toml_value, |old_value, piece| old_value.get(piece));
 
         match result_opt {
-            Some(val) => Self::toml_value_2_str(&val),
+            Some(val) => Self::toml_value_2_str(val),
             None => None,
         }
     }
@@ -775,7 +1296,7 @@ impl<'a> ConfigProvider<'a> {
             Value::Float(val) => Some(val.to_string()),
             Value::Array(val) => Some(
                 val.iter()
-                    .filter_map(|v| Self::toml_value_2_str(v))
+                    .filter_map(Self::toml_value_2_str)
                     .collect::<Vec<String>>()
                     .join(","),
             ),
@@ -783,3 +1304,71 @@ impl<'a> ConfigProvider<'a> {
         }
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use clap::{App, Arg};
+
+    fn tizen_env(config: &str, args: &[&str]) -> TizenEnv {
+        tizen_env_in(tempfile::tempdir().unwrap().path(), config, args)
+    }
+
+    // Creates a project in the folder, whose target can't be detected since sdb is
+    // not running and can't start, config is added to its [tizen] section
+    pub fn tizen_env_in(project: &Path, config: &str, args: &[&str]) -> TizenEnv {
+        fs::write(
+            project.join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"1.0.0\"\n",
+        )
+        .unwrap();
+        fs::write(
+            manifest_path(project),
+            r#"<manifest xmlns="http://tizen.org/ns/packages" api-version="5.5" package="org.example.app" version="1.0.0">
+    <profile name="wearable"/>
+    <ui-application appid="org.example.app" exec="app">
+        <label>App</label>
+    </ui-application>
+</manifest>
+"#,
+        )
+        .unwrap();
+        fs::create_dir(project.join(".cargo")).unwrap();
+        fs::write(
+            project.join(".cargo/config.toml"),
+            format!(
+                "[tizen]\nstudio_path = \"/nonexistent/tizen-studio\"\ntoolchain = \"llvm-10\"\nsdb_path = \"false\"\nsdb_port = 1\n{}",
+                config
+            ),
+        )
+        .unwrap();
+
+        let arg_matches = App::new("cargo-tizen")
+            .arg(Arg::with_name("emulator").short("e"))
+            .get_matches_from([&["cargo-tizen"], args].concat());
+
+        TizenEnv::from_cargo_config(project, &arg_matches, true).unwrap()
+    }
+
+    #[test]
+    fn emulator_arg_wins_over_the_config() {
+        assert!(tizen_env("is_emulator = false\n", &["-e"]).is_emulator);
+        assert!(!tizen_env("is_emulator = false\n", &[]).is_emulator);
+    }
+
+    #[test]
+    fn emulator_arg_wins_over_auto_detection() {
+        let tizen_env = tizen_env("is_emulator = \"auto\"\n", &["-e"]);
+
+        assert!(tizen_env.is_emulator);
+        assert_eq!(tizen_env.tizen_triple, "i586-linux-gnueabi");
+    }
+
+    #[test]
+    fn failed_auto_detection_uses_the_device() {
+        let tizen_env = tizen_env("is_emulator = \"auto\"\n", &[]);
+
+        assert!(!tizen_env.is_emulator);
+        assert_eq!(tizen_env.tizen_triple, "arm-linux-gnueabi");
+    }
+}
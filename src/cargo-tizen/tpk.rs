@@ -0,0 +1,78 @@
+use crate::error::TizenError;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+pub const TPK_DIRS: [&str; 4] = ["bin", "lib", "res", "shared"];
+
+pub fn write_tpk(
+    staging_dir: &Path,
+    tpk_path: &Path,
+    excludes: &[PathBuf],
+) -> Result<(), TizenError> {
+    let mut zip = ZipWriter::new(File::create(tpk_path)?);
+
+    for entry_path in list_entries(staging_dir, excludes)? {
+        let name = entry_name(staging_dir, &entry_path);
+
+        if entry_path.is_dir() {
+            zip.add_directory(
+                format!("{}/", &name),
+                FileOptions::default().unix_permissions(0o755),
+            )?;
+        } else {
+            let options = FileOptions::default()
+                .compression_method(CompressionMethod::Deflated)
+                .unix_permissions(if name.starts_with("bin/") {
+                    0o755
+                } else {
+                    0o644
+                });
+
+            zip.start_file(name, options)?;
+            io::copy(&mut File::open(&entry_path)?, &mut zip)?;
+        }
+    }
+
+    zip.finish()?;
+
+    Ok(())
+}
+
+pub fn list_entries(staging_dir: &Path, excludes: &[PathBuf]) -> Result<Vec<PathBuf>, TizenError> {
+    let mut entries: Vec<PathBuf> = vec![];
+    let mut stack: Vec<PathBuf> = vec![PathBuf::from(staging_dir)];
+
+    while let Some(working_path) = stack.pop() {
+        for entry in fs::read_dir(working_path)? {
+            let path = entry?.path();
+
+            if excludes.contains(&path) {
+                continue;
+            }
+
+            if path.is_dir() {
+                stack.push(path.clone());
+            }
+
+            entries.push(path);
+        }
+    }
+
+    entries.sort();
+
+    Ok(entries)
+}
+
+pub fn entry_name(staging_dir: &Path, entry_path: &Path) -> String {
+    entry_path
+        .strip_prefix(staging_dir)
+        .unwrap_or(entry_path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<String>>()
+        .join("/")
+}
@@ -0,0 +1,204 @@
+use crate::error::TizenError;
+use crate::helpers::wildcard_match;
+use std::fs;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+pub struct CargoWorkspace {
+    pub root: PathBuf,
+    pub package_path: PathBuf,
+}
+
+impl CargoWorkspace {
+    pub fn discover(cwd: &Path, package: Option<&str>) -> Result<Self, TizenError> {
+        let manifest_dir = match cwd.ancestors().find(|dir| dir.join("Cargo.toml").is_file()) {
+            Some(dir) => dir.to_path_buf(),
+            None => {
+                return Err(TizenError {
+                    message: format!(
+                        "File does not exists {}",
+                        cwd.join("Cargo.toml").to_str().unwrap_or("")
+                    ),
+                })
+            }
+        };
+
+        let manifest = read_cargo_toml(&manifest_dir)?;
+        let root = Self::find_root(&manifest_dir, &manifest);
+
+        let package_path = match package {
+            Some(package_name) => Self::find_member(&root, package_name)?,
+            None => {
+                if manifest.get("package").is_none() {
+                    return Err(TizenError {
+                        message: format!(
+                            "{} is a virtual manifest, select a package with -p, available packages: {}",
+                            manifest_dir.join("Cargo.toml").to_str().unwrap_or(""),
+                            Self::package_names(&root).join(", ")
+                        ),
+                    });
+                }
+
+                manifest_dir
+            }
+        };
+
+        Ok(Self { root, package_path })
+    }
+
+    fn find_root(manifest_dir: &Path, manifest: &Value) -> PathBuf {
+        if manifest.get("workspace").is_some() {
+            return manifest_dir.to_path_buf();
+        }
+
+        if let Some(workspace_path) = manifest
+            .get("package")
+            .and_then(|p| p.get("workspace"))
+            .and_then(|w| w.as_str())
+        {
+            return manifest_dir.join(workspace_path);
+        }
+
+        manifest_dir
+            .ancestors()
+            .skip(1)
+            .find(|dir| match read_cargo_toml(dir) {
+                Ok(parent_manifest) => {
+                    parent_manifest.get("workspace").is_some()
+                        && Self::members(dir, &parent_manifest)
+                            .iter()
+                            .any(|member| member == manifest_dir)
+                }
+                Err(_) => false,
+            })
+            .unwrap_or(manifest_dir)
+            .to_path_buf()
+    }
+
+    fn members(root: &Path, root_manifest: &Value) -> Vec<PathBuf> {
+        let workspace = match root_manifest.get("workspace") {
+            Some(workspace) => workspace,
+            None => return vec![],
+        };
+
+        let patterns = |key: &str| -> Vec<String> {
+            workspace
+                .get(key)
+                .and_then(|v| v.as_array())
+                .map(|v| {
+                    v.iter()
+                        .filter_map(|s| s.as_str())
+                        .map(|s| s.to_string())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let excludes: Vec<PathBuf> = patterns("exclude").iter().map(|e| root.join(e)).collect();
+
+        let mut members: Vec<PathBuf> = patterns("members")
+            .iter()
+            .flat_map(|pattern| expand_pattern(root, pattern))
+            .filter(|member| !excludes.contains(member))
+            .filter(|member| member.join("Cargo.toml").is_file())
+            .collect();
+
+        if root_manifest.get("package").is_some() {
+            members.insert(0, root.to_path_buf());
+        }
+
+        members
+    }
+
+    fn find_member(root: &Path, package_name: &str) -> Result<PathBuf, TizenError> {
+        let root_manifest = read_cargo_toml(root)?;
+
+        match Self::members(root, &root_manifest)
+            .into_iter()
+            .find(|member| package_name_of(member).as_deref() == Some(package_name))
+        {
+            Some(member) => Ok(member),
+            None => Err(TizenError {
+                message: format!(
+                    "Package '{}' not found in workspace, available packages: {}",
+                    package_name,
+                    Self::package_names(root).join(", ")
+                ),
+            }),
+        }
+    }
+
+    fn package_names(root: &Path) -> Vec<String> {
+        match read_cargo_toml(root) {
+            Ok(root_manifest) => Self::members(root, &root_manifest)
+                .iter()
+                .filter_map(|member| package_name_of(member))
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+}
+
+pub fn read_cargo_toml(dir: &Path) -> Result<Value, TizenError> {
+    let mut cargo_build_path = PathBuf::from(dir);
+    cargo_build_path.push("Cargo.toml");
+
+    match cargo_build_path.exists() {
+        true => match read_to_string(&cargo_build_path) {
+            Ok(content) => match content.parse::<Value>() {
+                Ok(toml_value) => Ok(toml_value),
+                Err(_) => Err(TizenError {
+                    message: format!("Can't parse {}", &cargo_build_path.to_str().unwrap_or("")),
+                }),
+            },
+            Err(_) => Err(TizenError {
+                message: format!("Can't read {}", &cargo_build_path.to_str().unwrap_or("")),
+            }),
+        },
+        false => Err(TizenError {
+            message: format!(
+                "File does not exists {}",
+                &cargo_build_path.to_str().unwrap_or("")
+            ),
+        }),
+    }
+}
+
+fn package_name_of(dir: &Path) -> Option<String> {
+    read_cargo_toml(dir).ok().and_then(|manifest| {
+        manifest
+            .get("package")
+            .and_then(|p| p.get("name"))
+            .and_then(|n| n.as_str())
+            .map(|n| n.to_string())
+    })
+}
+
+fn expand_pattern(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = vec![root.to_path_buf()];
+
+    for piece in pattern.split('/').filter(|p| !p.is_empty() && *p != ".") {
+        if !piece.contains('*') && !piece.contains('?') {
+            paths = paths.into_iter().map(|p| p.join(piece)).collect();
+            continue;
+        }
+
+        let mut expanded: Vec<PathBuf> = paths
+            .iter()
+            .filter_map(|p| fs::read_dir(p).ok())
+            .flat_map(|entries| entries.filter_map(|entry| entry.ok()))
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .filter(|path| match path.file_name().and_then(|n| n.to_str()) {
+                Some(name) => wildcard_match(piece, name),
+                None => false,
+            })
+            .collect();
+
+        expanded.sort();
+        paths = expanded;
+    }
+
+    paths
+}